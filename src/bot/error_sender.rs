@@ -1,18 +1,23 @@
-use std::env;
+use std::cmp::Reverse;
 
 use rand::Rng;
 use teloxide::prelude::*;
 use teloxide::types::*;
 
-use crate::global_data::get_bot;
+use crate::{
+    constants::error_reporting::{ERROR_GROUP_TTL_SECS, ERROR_KIND_MAX_LEN},
+    context::AppContext,
+    db::services::errors::{insert_error_reference, ErrorReference},
+    state::ErrorGroup,
+};
 
 use super::send_message;
 
-pub fn send_error(chat_id: ChatId, err_msg: &str, thread_id: Option<ThreadId>) {
+pub fn send_error(ctx: &AppContext, chat_id: ChatId, err_msg: &str, thread_id: Option<ThreadId>) {
     let format_err = format!("⚠️ Error: {err_msg}⚠️");
 
     info!("Handeled Err {format_err}");
-    send_message(chat_id, &format_err, thread_id);
+    send_message(ctx, chat_id, &format_err, thread_id);
 }
 // pub fn send_alert(callback_id: String, err_msg: &str) {
 //     let bot = get_bot();
@@ -27,32 +32,126 @@ pub fn send_error(chat_id: ChatId, err_msg: &str, thread_id: Option<ThreadId>) {
 //     });
 // }
 
-pub fn send_unexpected_error(user: &UserId, error: String) {
+pub fn send_unexpected_error(ctx: &AppContext, user: &UserId, error: String) {
     let mut rng = rand::thread_rng();
     let aleatory: u64 = rng.gen();
+    let reference = format!("{}:{:X}", user, aleatory);
     let format_err = format!(
-        "⚠️ Unexpected Error: Retry or ask suppor with ref : {}:{:X}⚠️",
-        user, aleatory
+        "⚠️ Unexpected Error: Retry or ask suppor with ref : {}⚠️",
+        reference
     );
 
     error!("Error no: {:X} for {user}. Value : \n {error}", aleatory);
-    let bot = get_bot();
-    let user_id = user.clone();
+    let messenger = ctx.messenger.clone();
+    let ctx = ctx.clone();
+    let user_id = *user;
 
-    tokio::spawn(async move {
-        let _ = bot
-            .send_message(user_id, format_err)
-            .parse_mode(ParseMode::Html)
+    messenger.spawn(Box::pin(async move {
+        // Kept so the reference can be looked up with /errorref
+        let error_ref = ErrorReference {
+            reference,
+            user_id: user_id.0 as i64,
+            message: error,
+            created_at: ctx.clock.now().timestamp(),
+        };
+        if let Err(e) = insert_error_reference(&ctx.pool, &error_ref).await {
+            error!("{}", e);
+        }
+        let _ = ctx
+            .messenger
+            .send_text(user_id.into(), format_err, None, Some(ParseMode::Html))
             .await
             .map_err(|e| error!("Error {}", e));
-    });
+    }));
 }
-pub fn send_error_to_moderator(error: String) {
-    let moderator_int: u64 = env::var("MODERATOR_ID")
-        .expect("Moderator ID not set")
-        .parse()
-        .expect("Moderator ID not a number");
-    send_unexpected_error(&UserId(moderator_int), error);
+
+fn moderator_id(ctx: &AppContext) -> UserId {
+    UserId(ctx.config.telegram.moderator_id)
+}
+
+/// Errors are grouped by kind, a kind is sent at most once per window,
+/// the repeats go in the periodic summary
+pub fn send_error_to_moderator(ctx: &AppContext, error: String) {
+    let messenger = ctx.messenger.clone();
+    let ctx = ctx.clone();
+    messenger.spawn(Box::pin(async move {
+        if record_error(&ctx, &error).await {
+            send_unexpected_error(&ctx, &moderator_id(&ctx), error);
+        } else {
+            debug!("Moderator error grouped: {}", error);
+        }
+    }));
+}
+
+/// True when the error should be sent now
+async fn record_error(ctx: &AppContext, error: &str) -> bool {
+    let now = ctx.clock.now().timestamp();
+    let mut groups = ctx.error_groups.lock().await;
+    let group = groups
+        .entry(error_kind(error))
+        .or_insert_with(ErrorGroup::default);
+    group.count += 1;
+    group.last_seen = now;
+    group.last_message = error.to_owned();
+    if now - group.notified_at >= ctx.config.rate_limits.error_window_secs {
+        group.notified_at = now;
+        true
+    } else {
+        group.suppressed += 1;
+        false
+    }
+}
+
+/// Same message with other numbers (ids, prices, times) is the same kind
+pub fn error_kind(error: &str) -> String {
+    let mut kind = String::new();
+    let mut in_number = false;
+    for c in error.chars().filter(|c| !c.is_control()) {
+        if c.is_ascii_digit() {
+            if !in_number {
+                kind.push('#');
+            }
+            in_number = true;
+        } else {
+            in_number = false;
+            kind.push(c);
+        }
+        if kind.chars().count() >= ERROR_KIND_MAX_LEN {
+            break;
+        }
+    }
+    kind
+}
+
+/// Send the grouped errors since the last summary, forget the idle kinds
+pub async fn send_error_summary(ctx: &AppContext) {
+    let now = ctx.clock.now().timestamp();
+    let mut lines: Vec<(u32, String)> = {
+        let mut groups = ctx.error_groups.lock().await;
+        groups.retain(|_, group| now - group.last_seen < ERROR_GROUP_TTL_SECS);
+        groups
+            .iter_mut()
+            .filter(|(_, group)| group.suppressed > 0)
+            .map(|(kind, group)| (std::mem::take(&mut group.suppressed), kind.clone()))
+            .collect()
+    };
+    if lines.is_empty() {
+        return;
+    }
+    lines.sort_by_key(|(count, _)| Reverse(*count));
+
+    let mut summary = String::from("Grouped errors since last summary:\n");
+    for (count, kind) in lines {
+        summary.push_str(&format!("{}× {}\n", count, kind));
+    }
+    let messenger = ctx.messenger.clone();
+    let moderator = moderator_id(ctx);
+    ctx.messenger.spawn(Box::pin(async move {
+        let _ = messenger
+            .send_text(moderator.into(), summary, None, None)
+            .await
+            .map_err(|e| error!("Error {}", e));
+    }));
 }
 // pub fn send_unexpected_callback_function_error(user: &User, callback: &str) {
 //     let user_id = user.id;
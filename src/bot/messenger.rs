@@ -0,0 +1,432 @@
+// src/bot/messenger.rs
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicI32, Ordering},
+        Mutex,
+    },
+};
+use teloxide::net::Download;
+use teloxide::prelude::*;
+use teloxide::types::*;
+use tokio::task::JoinHandle;
+
+/// What the bot can do to a chat, every sender in `bot` goes through it.
+/// Texts are already formatted, a keyboard, an edit or a caption is MarkdownV2.
+#[async_trait]
+pub trait Messenger: Send + Sync {
+    async fn send_text(
+        &self,
+        chat_id: ChatId,
+        text: String,
+        thread_id: Option<ThreadId>,
+        parse_mode: Option<ParseMode>,
+    ) -> anyhow::Result<MessageId>;
+
+    async fn send_with_keyboard(
+        &self,
+        chat_id: ChatId,
+        text: String,
+        thread_id: Option<ThreadId>,
+        keyboard: InlineKeyboardMarkup,
+    ) -> anyhow::Result<MessageId>;
+
+    async fn edit_text(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+        text: String,
+        keyboard: Option<InlineKeyboardMarkup>,
+    ) -> anyhow::Result<()>;
+
+    async fn delete(&self, chat_id: ChatId, message_id: MessageId) -> anyhow::Result<()>;
+
+    async fn send_photo(
+        &self,
+        chat_id: ChatId,
+        photo: InputFile,
+        caption: Option<String>,
+        thread_id: Option<ThreadId>,
+    ) -> anyhow::Result<MessageId>;
+
+    async fn send_document(
+        &self,
+        chat_id: ChatId,
+        file_name: String,
+        content: Vec<u8>,
+        thread_id: Option<ThreadId>,
+    ) -> anyhow::Result<MessageId>;
+
+    /// Content of a file sent to the bot, a document to import
+    async fn fetch_file(&self, file_id: String) -> anyhow::Result<Vec<u8>>;
+
+    /// Stops the button spinning, the text shows as a toast or as an alert to acknowledge
+    async fn answer_callback(
+        &self,
+        callback_id: String,
+        text: String,
+        show_alert: bool,
+    ) -> anyhow::Result<()>;
+
+    /// Runs a send in the background, the senders never wait for Telegram
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        tokio::spawn(task);
+    }
+}
+
+pub struct TelegramMessenger {
+    bot: Bot,
+}
+
+impl TelegramMessenger {
+    pub fn new(bot: Bot) -> Self {
+        Self { bot }
+    }
+}
+
+#[async_trait]
+impl Messenger for TelegramMessenger {
+    async fn send_text(
+        &self,
+        chat_id: ChatId,
+        text: String,
+        thread_id: Option<ThreadId>,
+        parse_mode: Option<ParseMode>,
+    ) -> anyhow::Result<MessageId> {
+        let mut request = self.bot.send_message(chat_id, text);
+        if let Some(mode) = parse_mode {
+            request = request.parse_mode(mode);
+        }
+        if let Some(id) = thread_id {
+            request = request.message_thread_id(id);
+        }
+        Ok(request.await?.id)
+    }
+
+    async fn send_with_keyboard(
+        &self,
+        chat_id: ChatId,
+        text: String,
+        thread_id: Option<ThreadId>,
+        keyboard: InlineKeyboardMarkup,
+    ) -> anyhow::Result<MessageId> {
+        let mut request = self
+            .bot
+            .send_message(chat_id, text)
+            .parse_mode(ParseMode::MarkdownV2)
+            .reply_markup(keyboard);
+        if let Some(id) = thread_id {
+            request = request.message_thread_id(id);
+        }
+        Ok(request.await?.id)
+    }
+
+    async fn edit_text(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+        text: String,
+        keyboard: Option<InlineKeyboardMarkup>,
+    ) -> anyhow::Result<()> {
+        let mut request = self
+            .bot
+            .edit_message_text(chat_id, message_id, text)
+            .parse_mode(ParseMode::MarkdownV2);
+        if let Some(keyboard) = keyboard {
+            request = request.reply_markup(keyboard);
+        }
+        request.await?;
+        Ok(())
+    }
+
+    async fn delete(&self, chat_id: ChatId, message_id: MessageId) -> anyhow::Result<()> {
+        self.bot.delete_message(chat_id, message_id).await?;
+        Ok(())
+    }
+
+    async fn send_photo(
+        &self,
+        chat_id: ChatId,
+        photo: InputFile,
+        caption: Option<String>,
+        thread_id: Option<ThreadId>,
+    ) -> anyhow::Result<MessageId> {
+        let mut request = self.bot.send_photo(chat_id, photo);
+        if let Some(caption) = caption {
+            request = request.caption(caption).parse_mode(ParseMode::MarkdownV2);
+        }
+        if let Some(id) = thread_id {
+            request = request.message_thread_id(id);
+        }
+        Ok(request.await?.id)
+    }
+
+    async fn send_document(
+        &self,
+        chat_id: ChatId,
+        file_name: String,
+        content: Vec<u8>,
+        thread_id: Option<ThreadId>,
+    ) -> anyhow::Result<MessageId> {
+        let mut request = self
+            .bot
+            .send_document(chat_id, InputFile::memory(content).file_name(file_name));
+        if let Some(id) = thread_id {
+            request = request.message_thread_id(id);
+        }
+        Ok(request.await?.id)
+    }
+
+    async fn fetch_file(&self, file_id: String) -> anyhow::Result<Vec<u8>> {
+        let file = self.bot.get_file(file_id).await?;
+        let mut content = Vec::new();
+        self.bot.download_file(&file.path, &mut content).await?;
+        Ok(content)
+    }
+
+    async fn answer_callback(
+        &self,
+        callback_id: String,
+        text: String,
+        show_alert: bool,
+    ) -> anyhow::Result<()> {
+        let mut request = self.bot.answer_callback_query(callback_id);
+        if !text.is_empty() {
+            request = request.text(text).show_alert(show_alert);
+        }
+        request.await?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordedAction {
+    Sent,
+    Edited,
+    Deleted,
+    Photo,
+    Document,
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordedMessage {
+    pub action: RecordedAction,
+    pub chat_id: ChatId,
+    pub thread_id: Option<ThreadId>,
+    pub message_id: MessageId,
+    pub text: String, // caption for a photo, file name for a document, empty for a delete
+    pub keyboard: Option<InlineKeyboardMarkup>,
+    pub content: Option<Vec<u8>>, // document only
+}
+
+impl RecordedMessage {
+    fn new(action: RecordedAction, chat_id: ChatId, message_id: MessageId, text: String) -> Self {
+        Self {
+            action,
+            chat_id,
+            thread_id: None,
+            message_id,
+            text,
+            keyboard: None,
+            content: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RecordedAnswer {
+    pub callback_id: String,
+    pub text: String,
+    pub show_alert: bool,
+}
+
+/// Keeps everything in memory instead of calling Telegram, for the tests
+#[derive(Default)]
+pub struct RecordingMessenger {
+    recorded: Mutex<Vec<RecordedMessage>>,
+    answers: Mutex<Vec<RecordedAnswer>>,
+    files: Mutex<HashMap<String, Vec<u8>>>,
+    pending: Mutex<Vec<JoinHandle<()>>>,
+    last_id: AtomicI32,
+}
+
+impl RecordingMessenger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn recorded(&self) -> Vec<RecordedMessage> {
+        self.recorded.lock().unwrap().clone()
+    }
+
+    /// Texts, photos and documents that reached the chat, in sending order
+    pub fn sent_to(&self, chat_id: ChatId) -> Vec<RecordedMessage> {
+        self.recorded()
+            .into_iter()
+            .filter(|m| m.chat_id == chat_id)
+            .filter(|m| {
+                matches!(
+                    m.action,
+                    RecordedAction::Sent | RecordedAction::Photo | RecordedAction::Document
+                )
+            })
+            .collect()
+    }
+
+    /// Callback queries answered, in order
+    pub fn answers(&self) -> Vec<RecordedAnswer> {
+        self.answers.lock().unwrap().clone()
+    }
+
+    pub fn clear(&self) {
+        self.recorded.lock().unwrap().clear();
+        self.answers.lock().unwrap().clear();
+    }
+
+    /// Makes a file available to `fetch_file`, as if a user had sent it
+    pub fn add_file(&self, file_id: &str, content: Vec<u8>) {
+        self.files
+            .lock()
+            .unwrap()
+            .insert(file_id.to_owned(), content);
+    }
+
+    /// Waits for the background sends, and for the ones they started
+    pub async fn flush(&self) {
+        loop {
+            let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+            if pending.is_empty() {
+                return;
+            }
+            for task in pending {
+                task.await.expect("A background send panicked");
+            }
+        }
+    }
+
+    fn next_id(&self) -> MessageId {
+        MessageId(self.last_id.fetch_add(1, Ordering::SeqCst) + 1)
+    }
+
+    fn record(&self, message: RecordedMessage) -> MessageId {
+        let message_id = message.message_id;
+        self.recorded.lock().unwrap().push(message);
+        message_id
+    }
+}
+
+#[async_trait]
+impl Messenger for RecordingMessenger {
+    async fn send_text(
+        &self,
+        chat_id: ChatId,
+        text: String,
+        thread_id: Option<ThreadId>,
+        _parse_mode: Option<ParseMode>,
+    ) -> anyhow::Result<MessageId> {
+        Ok(self.record(RecordedMessage {
+            thread_id,
+            ..RecordedMessage::new(RecordedAction::Sent, chat_id, self.next_id(), text)
+        }))
+    }
+
+    async fn send_with_keyboard(
+        &self,
+        chat_id: ChatId,
+        text: String,
+        thread_id: Option<ThreadId>,
+        keyboard: InlineKeyboardMarkup,
+    ) -> anyhow::Result<MessageId> {
+        Ok(self.record(RecordedMessage {
+            thread_id,
+            keyboard: Some(keyboard),
+            ..RecordedMessage::new(RecordedAction::Sent, chat_id, self.next_id(), text)
+        }))
+    }
+
+    async fn edit_text(
+        &self,
+        chat_id: ChatId,
+        message_id: MessageId,
+        text: String,
+        keyboard: Option<InlineKeyboardMarkup>,
+    ) -> anyhow::Result<()> {
+        self.record(RecordedMessage {
+            keyboard,
+            ..RecordedMessage::new(RecordedAction::Edited, chat_id, message_id, text)
+        });
+        Ok(())
+    }
+
+    async fn delete(&self, chat_id: ChatId, message_id: MessageId) -> anyhow::Result<()> {
+        self.record(RecordedMessage::new(
+            RecordedAction::Deleted,
+            chat_id,
+            message_id,
+            String::new(),
+        ));
+        Ok(())
+    }
+
+    async fn send_photo(
+        &self,
+        chat_id: ChatId,
+        _photo: InputFile,
+        caption: Option<String>,
+        thread_id: Option<ThreadId>,
+    ) -> anyhow::Result<MessageId> {
+        Ok(self.record(RecordedMessage {
+            thread_id,
+            ..RecordedMessage::new(
+                RecordedAction::Photo,
+                chat_id,
+                self.next_id(),
+                caption.unwrap_or_default(),
+            )
+        }))
+    }
+
+    async fn send_document(
+        &self,
+        chat_id: ChatId,
+        file_name: String,
+        content: Vec<u8>,
+        thread_id: Option<ThreadId>,
+    ) -> anyhow::Result<MessageId> {
+        Ok(self.record(RecordedMessage {
+            thread_id,
+            content: Some(content),
+            ..RecordedMessage::new(RecordedAction::Document, chat_id, self.next_id(), file_name)
+        }))
+    }
+
+    async fn fetch_file(&self, file_id: String) -> anyhow::Result<Vec<u8>> {
+        self.files
+            .lock()
+            .unwrap()
+            .get(&file_id)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No file {}", file_id))
+    }
+
+    async fn answer_callback(
+        &self,
+        callback_id: String,
+        text: String,
+        show_alert: bool,
+    ) -> anyhow::Result<()> {
+        self.answers.lock().unwrap().push(RecordedAnswer {
+            callback_id,
+            text,
+            show_alert,
+        });
+        Ok(())
+    }
+
+    fn spawn(&self, task: BoxFuture<'static, ()>) {
+        self.pending.lock().unwrap().push(tokio::spawn(task));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod commands;
 pub mod error_sender;
+pub mod messenger;
 pub mod msg_delete;
 pub mod msg_modifiers;
 pub mod msg_senders;
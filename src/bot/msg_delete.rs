@@ -1,15 +1,15 @@
 use teloxide::prelude::*;
 
-use crate::global_data::get_bot;
+use crate::context::AppContext;
 
-pub fn delete_message(msg: &Message, chat_id: ChatId) {
-    let bot = get_bot(); // no arc as per doc
-    let msg_id = msg.id.clone();
+pub fn delete_message(ctx: &AppContext, msg: &Message, chat_id: ChatId) {
+    let messenger = ctx.messenger.clone();
+    let msg_id = msg.id;
 
-    tokio::spawn(async move {
-        let _ = bot
-            .delete_message(chat_id, msg_id)
+    ctx.messenger.spawn(Box::pin(async move {
+        let _ = messenger
+            .delete(chat_id, msg_id)
             .await
             .map_err(|e| error!("Error {}", e));
-    });
+    }));
 }
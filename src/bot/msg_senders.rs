@@ -3,96 +3,175 @@ use teloxide::prelude::*;
 use teloxide::types::*;
 
 use crate::bot::utils::parse_msg_for_tg;
-use crate::global_data::get_bot;
+use crate::context::AppContext;
+use crate::metrics::{record_alert, TELEGRAM_ERRORS};
 
 pub fn send_message_with_button(
+    ctx: &AppContext,
     chat_id: ChatId,
     msg_to_send: &str,
     thread_id: Option<ThreadId>,
     keyboard: InlineKeyboardMarkup,
 ) {
     let msg_to_send = parse_msg_for_tg(msg_to_send.to_owned());
-    let bot = get_bot();
-    if let Some(id) = thread_id {
-        tokio::spawn(async move {
-            let _ = bot
-                .send_message(chat_id, msg_to_send)
-                .parse_mode(ParseMode::MarkdownV2)
-                .message_thread_id(id)
-                .reply_markup(keyboard)
-                .await
-                .map_err(|e| error!("Error sending thread message {}", e));
-        });
-    } else {
-        tokio::spawn(async move {
-            let _ = bot
-                .send_message(chat_id, msg_to_send)
-                .parse_mode(ParseMode::MarkdownV2)
-                .reply_markup(keyboard)
-                .await
-                .map_err(|e| error!("Error sending message {}", e));
-        });
-    }
+    let messenger = ctx.messenger.clone();
+    ctx.messenger.spawn(Box::pin(async move {
+        if let Err(e) = messenger
+            .send_with_keyboard(chat_id, msg_to_send, thread_id, keyboard)
+            .await
+        {
+            TELEGRAM_ERRORS.with_label_values(&["send_message"]).inc();
+            error!("Error sending message {}", e);
+        }
+    }));
 }
 
-pub fn send_message(chat_id: ChatId, msg_to_send: &str, thread_id: Option<ThreadId>) {
+pub fn send_message(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    msg_to_send: &str,
+    thread_id: Option<ThreadId>,
+) {
+    spawn_send(ctx, chat_id, msg_to_send, thread_id, None);
+}
+
+/// Same as `send_message`, counted in the alert metrics under `kind`
+pub fn send_alert(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    msg_to_send: &str,
+    thread_id: Option<ThreadId>,
+    kind: &str,
+) {
+    spawn_send(ctx, chat_id, msg_to_send, thread_id, Some(kind.to_owned()));
+}
+
+fn spawn_send(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    msg_to_send: &str,
+    thread_id: Option<ThreadId>,
+    kind: Option<String>,
+) {
     let msg_to_send = parse_msg_for_tg(msg_to_send.to_owned());
-    let bot = get_bot();
-    if let Some(id) = thread_id {
-        tokio::spawn(async move {
-            let _ = bot
-                .send_message(chat_id, msg_to_send)
-                .parse_mode(ParseMode::MarkdownV2)
-                .message_thread_id(id)
-                .await
-                .map_err(|e| error!("Error sending thread message {}", e));
-        });
-    } else {
-        tokio::spawn(async move {
-            let _ = bot
-                .send_message(chat_id, msg_to_send)
-                .parse_mode(ParseMode::MarkdownV2)
-                .await
-                .map_err(|e| error!("Error sending message {}", e));
-        });
-    }
+    let messenger = ctx.messenger.clone();
+    ctx.messenger.spawn(Box::pin(async move {
+        let result = messenger
+            .send_text(chat_id, msg_to_send, thread_id, Some(ParseMode::MarkdownV2))
+            .await;
+        if let Some(kind) = kind {
+            record_alert(&kind, result.is_ok());
+        }
+        if let Err(e) = result {
+            TELEGRAM_ERRORS.with_label_values(&["send_message"]).inc();
+            error!("Error sending message {}", e);
+        }
+    }));
+}
+
+/// Deleted `lifetime` after being sent, for replies only the sender needs
+pub fn send_ephemeral(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    msg_to_send: &str,
+    thread_id: Option<ThreadId>,
+    lifetime: std::time::Duration,
+) {
+    let msg_to_send = parse_msg_for_tg(msg_to_send.to_owned());
+    let messenger = ctx.messenger.clone();
+    ctx.messenger.spawn(Box::pin(async move {
+        let message_id = match messenger
+            .send_text(chat_id, msg_to_send, thread_id, Some(ParseMode::MarkdownV2))
+            .await
+        {
+            Ok(message_id) => message_id,
+            Err(e) => {
+                TELEGRAM_ERRORS.with_label_values(&["send_message"]).inc();
+                error!("Error sending message {}", e);
+                return;
+            }
+        };
+        tokio::time::sleep(lifetime).await;
+        if let Err(e) = messenger.delete(chat_id, message_id).await {
+            error!("Error deleting message {}", e);
+        }
+    }));
 }
 
-pub async fn broadcast_message(chat_ids: Vec<i64>, message: String) -> anyhow::Result<()> {
+/// Acknowledges a button press, see `Messenger::answer_callback`
+pub fn answer_callback(ctx: &AppContext, callback_id: String, text: String, show_alert: bool) {
+    let messenger = ctx.messenger.clone();
+    ctx.messenger.spawn(Box::pin(async move {
+        if let Err(e) = messenger
+            .answer_callback(callback_id, text, show_alert)
+            .await
+        {
+            TELEGRAM_ERRORS
+                .with_label_values(&["answer_callback"])
+                .inc();
+            error!("Error answering callback {}", e);
+        }
+    }));
+}
+
+pub fn send_document(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    file_name: String,
+    content: Vec<u8>,
+    thread_id: Option<ThreadId>,
+) {
+    let messenger = ctx.messenger.clone();
+    ctx.messenger.spawn(Box::pin(async move {
+        if let Err(e) = messenger
+            .send_document(chat_id, file_name, content, thread_id)
+            .await
+        {
+            TELEGRAM_ERRORS.with_label_values(&["send_document"]).inc();
+            error!("Error sending document {}", e);
+        }
+    }));
+}
+
+pub async fn broadcast_message(
+    ctx: &AppContext,
+    chat_ids: Vec<i64>,
+    message: String,
+    kind: &str,
+) -> anyhow::Result<()> {
     let msg_to_send = parse_msg_for_tg(message);
-    const MAX_CONCURRENT: usize = 5;
-    const RATE_LIMIT_MS: u64 = 50;
+    let rate_limits = &ctx.config.rate_limits;
 
-    let bot = get_bot();
-    let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(RATE_LIMIT_MS));
+    let mut interval = tokio::time::interval(tokio::time::Duration::from_millis(
+        rate_limits.broadcast_interval_ms,
+    ));
     let mut tasks = FuturesUnordered::new();
+    let mut error = false;
 
     for chat_id in chat_ids {
         interval.tick().await;
 
-        while tasks.len() >= MAX_CONCURRENT {
+        while tasks.len() >= rate_limits.broadcast_concurrency {
             if let Some(result) = tasks.next().await {
-                if let Err(e) = result {
-                    error!("Message send error: {}", e);
+                if !record_broadcast_result(kind, result) {
+                    error = true;
                 }
             }
         }
 
-        let bot = bot.clone();
+        let messenger = ctx.messenger.clone();
         let message = msg_to_send.clone();
         tasks.push(tokio::spawn(async move {
-            bot.send_message(ChatId(chat_id), message)
-                .parse_mode(ParseMode::MarkdownV2)
+            messenger
+                .send_text(ChatId(chat_id), message, None, Some(ParseMode::MarkdownV2))
                 .await
         }));
     }
 
     // Wait for remaining tasks
-    let mut error = false;
     while let Some(result) = tasks.next().await {
-        if let Err(e) = result {
+        if !record_broadcast_result(kind, result) {
             error = true;
-            error!("Message send error: {}", e);
         }
     }
     if !error {
@@ -101,3 +180,27 @@ pub async fn broadcast_message(chat_ids: Vec<i64>, message: String) -> anyhow::R
         Err(anyhow::anyhow!("Error broadcasting message"))
     }
 }
+
+/// True when the message was sent, refused and failed sends fail the broadcast
+fn record_broadcast_result(
+    kind: &str,
+    result: Result<anyhow::Result<MessageId>, tokio::task::JoinError>,
+) -> bool {
+    match result {
+        Ok(Ok(_)) => {
+            record_alert(kind, true);
+            true
+        }
+        Ok(Err(e)) => {
+            TELEGRAM_ERRORS.with_label_values(&["send_message"]).inc();
+            record_alert(kind, false);
+            error!("Message send error: {}", e);
+            false
+        }
+        Err(e) => {
+            record_alert(kind, false);
+            error!("Message send error: {}", e);
+            false
+        }
+    }
+}
@@ -6,3 +6,44 @@ pub fn parse_msg_for_tg(message: String) -> String {
         // .replace('`', "\\`")
         .replace('.', "\\.")
 }
+
+/// Escape text we don't control (token names, full names) for MarkdownV2.
+/// `-` and `.` are left to `parse_msg_for_tg`.
+pub fn escape_user_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "_*[]()~`>#+=|{}!\\".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Drop the MarkdownV2 escapes and code marks, for texts shown without parse mode (toasts)
+pub fn plain_text(text: &str) -> String {
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => plain.extend(chars.next()),
+            '`' => {}
+            c => plain.push(c),
+        }
+    }
+    plain
+}
+
+/// 1234567.0 -> "1.23M$"
+pub fn format_usd_compact(value: f64) -> String {
+    let abs = value.abs();
+    if abs >= 1e9 {
+        format!("{:.2}B$", value / 1e9)
+    } else if abs >= 1e6 {
+        format!("{:.2}M$", value / 1e6)
+    } else if abs >= 1e3 {
+        format!("{:.1}k$", value / 1e3)
+    } else {
+        format!("{:.0}$", value)
+    }
+}
@@ -0,0 +1,298 @@
+// src/cli.rs
+
+use anyhow::anyhow;
+use clap::{Args, Parser, Subcommand};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Write},
+    path::PathBuf,
+};
+use teloxide::types::{ChatId, MessageId, ParseMode, ThreadId};
+
+use crate::{
+    bot::utils::parse_msg_for_tg,
+    config::Config,
+    constants::schedules::parse_interval,
+    context::AppContext,
+    db::{
+        init_bdd::check_and_init_db,
+        services::demands::{get_demands_by_chat_id, Demand},
+    },
+    error::BotError,
+    handlers::transfer::{demand_rows, validate_rows},
+    hyperliquid::fetch_price::{fetch_token_data, TokenInfo},
+    init::init_pool,
+    procedures::replay::{run_replay, ReplayOptions, ReplaySource},
+    state::{update_perp_data, update_token_data, TokenMapping},
+    types::{
+        commands::ALERT,
+        demand_file::{decode, encode, DemandFileFormat},
+    },
+};
+
+#[derive(Parser)]
+#[command(name = "wagmi_alert", about = "Hyperliquid alert bot for Telegram")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Start the bot, the scheduled jobs and the HTTP endpoints (default)
+    Run,
+    /// Create the tables on an empty database or apply the pending migrations
+    Migrate,
+    /// Fetch the spot tokens once and print them as JSON
+    FetchOnce,
+    /// Print the demands of a chat
+    ListDemands {
+        #[arg(long)]
+        chat: i64,
+    },
+    /// Send a message to a chat to check the bot can post there
+    SendTest {
+        #[arg(long)]
+        chat: i64,
+        #[arg(long)]
+        thread: Option<i32>,
+        #[arg(long, default_value = "Test message from the WAGMI bot")]
+        text: String,
+    },
+    /// Write the demands of a chat in the /export format
+    Export {
+        #[arg(long)]
+        chat: i64,
+        /// json or csv, taken from the output extension when not given
+        #[arg(long)]
+        format: Option<String>,
+        #[arg(long)]
+        output: Option<PathBuf>, // stdout by default
+    },
+    /// Add the demands of an /export file to a chat, the existing ones are skipped
+    Import {
+        file: PathBuf,
+        #[arg(long)]
+        chat: i64,
+        #[arg(long)]
+        thread: Option<i32>,
+        /// json or csv, taken from the file extension when not given
+        #[arg(long)]
+        format: Option<String>,
+    },
+    /// Print the alerts the history would have fired, nothing is sent
+    Replay(ReplayArgs),
+}
+
+impl CliCommand {
+    /// Commands building the bot, the others run without a Telegram token
+    pub fn needs_telegram(&self) -> bool {
+        matches!(
+            self,
+            CliCommand::Run | CliCommand::SendTest { .. } | CliCommand::Import { .. }
+        )
+    }
+}
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// Replay the demands of this chat
+    #[arg(long)]
+    chat: Option<i64>,
+    /// Token index or name of a demand to try, with --percentage and --interval
+    #[arg(long)]
+    token: Option<String>,
+    #[arg(long)]
+    percentage: Option<i16>,
+    #[arg(long)]
+    interval: Option<String>,
+    /// Replay the pump check
+    #[arg(long)]
+    pump: bool,
+    /// Lines of timestamp,token,price[,market_cap] instead of the stored snapshots
+    #[arg(long, conflicts_with_all = ["since", "until"])]
+    candles: Option<PathBuf>,
+    /// Unix time of the first stored snapshot
+    #[arg(long)]
+    since: Option<i64>,
+    /// Unix time of the last stored snapshot
+    #[arg(long)]
+    until: Option<i64>,
+}
+
+/// The format given, or the one of the file name, JSON by default
+fn file_format(format: Option<String>, path: Option<&PathBuf>) -> anyhow::Result<DemandFileFormat> {
+    match format {
+        Some(format) => {
+            DemandFileFormat::parse(&format).ok_or_else(|| anyhow!("Unknown format: {}", format))
+        }
+        None => Ok(path
+            .and_then(|path| path.file_name()?.to_str())
+            .and_then(DemandFileFormat::from_file_name)
+            .unwrap_or(DemandFileFormat::Json)),
+    }
+}
+
+impl ReplayArgs {
+    fn into_options(self) -> anyhow::Result<ReplayOptions> {
+        let demand = match (self.token, self.percentage, self.interval) {
+            (Some(token), Some(percentage), Some(interval)) => {
+                let interval = parse_interval(&interval)
+                    .ok_or_else(|| anyhow!("Invalid interval: {}", interval))?;
+                Some(Demand {
+                    token,
+                    percentage,
+                    interval: interval.to_owned(),
+                    ..Demand::new(0, ALERT, None)
+                })
+            }
+            (None, None, None) => None,
+            _ => return Err(anyhow!("--token, --percentage and --interval go together")),
+        };
+        if self.chat.is_none() && demand.is_none() && !self.pump {
+            return Err(anyhow!(
+                "Nothing to replay, give --chat, a demand or --pump"
+            ));
+        }
+        let source = match self.candles {
+            Some(path) => ReplaySource::Candles(path),
+            None => ReplaySource::Database {
+                since: self.since,
+                until: self.until,
+            },
+        };
+
+        Ok(ReplayOptions {
+            source,
+            chat_id: self.chat,
+            demand,
+            pump: self.pump,
+        })
+    }
+}
+
+/// Every command but `run`, which stays in `main`
+pub async fn execute(config: Config, command: CliCommand) -> anyhow::Result<()> {
+    match command {
+        CliCommand::Run => unreachable!("run is started by main"),
+        CliCommand::Migrate => {
+            let pool = init_pool(&config.database.url).await?;
+            let done = check_and_init_db(&pool, &config.hyperliquid.info_url).await?;
+            if done.is_empty() {
+                println!("Database up to date");
+            }
+            for step in done {
+                println!("{}", step);
+            }
+        }
+        CliCommand::FetchOnce => {
+            let (tokens, _, report) = fetch_token_data(&config.hyperliquid.info_url).await?;
+            // Keys are token indexes, sorted as numbers
+            let tokens: BTreeMap<usize, TokenInfo> = tokens
+                .into_iter()
+                .filter_map(|(key, token)| Some((key.parse().ok()?, token)))
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&tokens)?);
+            for failed in report.failed.iter() {
+                eprintln!("skipped: {}", failed);
+            }
+        }
+        CliCommand::ListDemands { chat } => {
+            let pool = init_pool(&config.database.url).await?;
+            let demands = get_demands_by_chat_id(&pool, chat).await?;
+            if demands.is_empty() {
+                println!("No demand for chat {}", chat);
+            }
+            for demand in demands {
+                println!(
+                    "{:<14}{:<10}{:>6}  {:<8}thread {:<8}{}",
+                    demand.type_of,
+                    demand.token,
+                    demand.percentage,
+                    demand.interval,
+                    demand.thread_id.map_or("-".to_owned(), |id| id.to_string()),
+                    demand.label.unwrap_or_default()
+                );
+            }
+        }
+        CliCommand::SendTest { chat, thread, text } => {
+            let ctx = AppContext::new(config).await?;
+            let message_id = ctx
+                .messenger
+                .send_text(
+                    ChatId(chat),
+                    parse_msg_for_tg(text),
+                    thread.map(|id| ThreadId(MessageId(id))),
+                    Some(ParseMode::MarkdownV2),
+                )
+                .await?;
+            println!("Sent, message id {}", message_id.0);
+        }
+        CliCommand::Export {
+            chat,
+            format,
+            output,
+        } => {
+            let format = file_format(format, output.as_ref())?;
+            let pool = init_pool(&config.database.url).await?;
+            let demands = get_demands_by_chat_id(&pool, chat).await?;
+            // Names are informative, the export goes on without them
+            let token_map = match fetch_token_data(&config.hyperliquid.info_url).await {
+                Ok((tokens, _, _)) => tokens,
+                Err(e) => {
+                    eprintln!("no token names: {}", e);
+                    TokenMapping::new()
+                }
+            };
+            let rows = demand_rows(demands, &token_map);
+            let content = encode(&rows, format)?;
+            match output {
+                Some(path) => {
+                    fs::write(&path, content)?;
+                    eprintln!("{} demands written to {}", rows.len(), path.display());
+                }
+                None => io::stdout().write_all(&content)?,
+            }
+        }
+        CliCommand::Import {
+            file,
+            chat,
+            thread,
+            format,
+        } => {
+            let format = file_format(format, Some(&file))?;
+            let content =
+                fs::read(&file).map_err(|e| anyhow!("Cannot read {}: {}", file.display(), e))?;
+            let rows = decode(&content, format)?;
+            let ctx = AppContext::new(config).await?;
+            update_token_data(&ctx).await?;
+            update_perp_data(&ctx).await?;
+            ctx.chat_demands.reload(&ctx.pool).await?;
+            let demands = validate_rows(
+                rows,
+                &ctx.market.universe().await,
+                &ctx.market.perps().await,
+                chat,
+                thread,
+            )
+            .map_err(|e| anyhow!(e))?;
+
+            // Operators import past the chat quota
+            let (mut added, mut skipped) = (0, 0);
+            for demand in demands {
+                match demand.insert_to_db(&ctx).await {
+                    Ok(()) => added += 1,
+                    Err(BotError::UserInput(_)) => skipped += 1, // already there
+                    Err(e) => return Err(e.into()),
+                }
+            }
+            println!("{} demands added, {} already there", added, skipped);
+        }
+        CliCommand::Replay(args) => {
+            let report = run_replay(&config, args.into_options()?).await?;
+            report.print();
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,507 @@
+// src/config.rs
+
+use chrono::{Duration, TimeZone, Utc};
+use cron_clock::Schedule;
+use serde::Deserialize;
+use std::{collections::BTreeMap, env, fmt, path::Path, str::FromStr};
+
+use crate::constants::{
+    error_reporting::ERROR_WINDOW_SECS,
+    liquidity::DEPTH_RANGE_PERCENT,
+    market_cap::MAX_SUPPLY_FETCH_PER_RUN,
+    permissions::ADMIN_CACHE_TTL_SECS,
+    pumpcheck::{MIN_MARKET_CAP, OVER_SPECIAL_PERCENTAGE, SPECIAL_PERCENTAGE},
+    schedules::{INTERVALS, INTERVAL_15MIN},
+    wallet_watch::{LARGE_TRANSFER_USD, MAX_FILLS_PER_MESSAGE, MIN_HOLDING_CHANGE_PERCENT},
+};
+
+const DEFAULT_CONFIG_PATH: &str = "config.toml";
+const ALIGNMENT_CHECKS: usize = 64; // firings compared, enough to cover the weekly schedules
+
+/// Loaded once at startup from `CONFIG_PATH` (default `config.toml`),
+/// then overridden by the environment. Missing values keep the defaults below.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub telegram: TelegramConfig,
+    pub permissions: PermissionsConfig,
+    pub database: DatabaseConfig,
+    pub http: HttpConfig,
+    pub hyperliquid: HyperliquidConfig,
+    pub links: LinksConfig,
+    pub pump: PumpConfig,
+    pub thresholds: ThresholdsConfig,
+    pub quotas: QuotasConfig,
+    pub rate_limits: RateLimitsConfig,
+    pub intervals: IntervalsConfig,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelegramConfig {
+    pub token: String,          // TELOXIDE_TOKEN
+    pub moderator_id: u64,      // MODERATOR_ID
+    pub operator_ids: Vec<u64>, // OPERATOR_IDS, comma separated, defaults to the moderator
+}
+
+/// Who can manage the alerts of a chat: its owner, its managers (`/manager` or `manager_ids`)
+/// and its administrators unless `admins_manage_alerts` is off.
+/// The others are told so in a reply deleted after `non_admin_reply_secs`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PermissionsConfig {
+    pub admins_manage_alerts: bool,
+    pub manager_ids: BTreeMap<String, Vec<u64>>, // user IDs by chat ID
+    pub admin_cache_ttl_secs: i64,
+    pub non_admin_reply_secs: u64, // 0 ignores the commands of the others silently
+}
+
+impl PermissionsConfig {
+    pub fn is_manager(&self, chat_id: i64, user_id: u64) -> bool {
+        self.manager_ids
+            .get(&chat_id.to_string())
+            .is_some_and(|ids| ids.contains(&user_id))
+    }
+}
+
+impl Default for PermissionsConfig {
+    fn default() -> Self {
+        Self {
+            admins_manage_alerts: true,
+            manager_ids: BTreeMap::new(),
+            admin_cache_ttl_secs: ADMIN_CACHE_TTL_SECS,
+            non_admin_reply_secs: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String, // DATABASE_URL
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    pub addr: String, // HTTP_ADDR
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            addr: "0.0.0.0:9090".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HyperliquidConfig {
+    pub info_url: String, // HYPERLIQUID_INFO_URL
+}
+
+impl Default for HyperliquidConfig {
+    fn default() -> Self {
+        Self {
+            info_url: "https://api.hyperliquid.xyz/info".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LinksConfig {
+    pub referral: String, // REFERRAL_LINK, the pair number is appended
+}
+
+impl Default for LinksConfig {
+    fn default() -> Self {
+        Self {
+            referral: "https://t.me/HypurrFunBot?start=ref_2262836c-trade_".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PumpConfig {
+    pub special_percentage: f64,
+    pub over_special_percentage: f64,
+    pub min_market_cap: f64,
+}
+
+impl Default for PumpConfig {
+    fn default() -> Self {
+        Self {
+            special_percentage: SPECIAL_PERCENTAGE,
+            over_special_percentage: OVER_SPECIAL_PERCENTAGE,
+            min_market_cap: MIN_MARKET_CAP,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThresholdsConfig {
+    pub depth_range_percent: f64,
+    pub large_transfer_usd: f64,
+    pub min_holding_change_percent: f64,
+}
+
+impl Default for ThresholdsConfig {
+    fn default() -> Self {
+        Self {
+            depth_range_percent: DEPTH_RANGE_PERCENT,
+            large_transfer_usd: LARGE_TRANSFER_USD,
+            min_holding_change_percent: MIN_HOLDING_CHANGE_PERCENT,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuotasConfig {
+    pub max_demands_per_chat: u8,
+    pub max_fills_per_message: usize,
+    pub max_supply_fetch_per_run: usize,
+}
+
+impl Default for QuotasConfig {
+    fn default() -> Self {
+        Self {
+            max_demands_per_chat: 3,
+            max_fills_per_message: MAX_FILLS_PER_MESSAGE,
+            max_supply_fetch_per_run: MAX_SUPPLY_FETCH_PER_RUN,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitsConfig {
+    pub broadcast_concurrency: usize,
+    pub broadcast_interval_ms: u64,
+    pub error_window_secs: i64,
+}
+
+impl Default for RateLimitsConfig {
+    fn default() -> Self {
+        Self {
+            broadcast_concurrency: 5,
+            broadcast_interval_ms: 50,
+            error_window_secs: ERROR_WINDOW_SECS,
+        }
+    }
+}
+
+/// Cron expression of each interval, by interval name
+#[derive(Debug, Clone, Deserialize)]
+#[serde(transparent)]
+pub struct IntervalsConfig(pub BTreeMap<String, String>);
+
+impl Default for IntervalsConfig {
+    fn default() -> Self {
+        Self(
+            INTERVALS
+                .iter()
+                .map(|(name, cron)| (name.to_string(), cron.to_string()))
+                .collect(),
+        )
+    }
+}
+
+impl IntervalsConfig {
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .iter()
+            .map(|(name, cron)| (name.as_str(), cron.as_str()))
+    }
+
+    pub fn cron(&self, name: &str) -> Option<&str> {
+        self.0.get(name).map(|cron| cron.as_str())
+    }
+
+    /// A file listing some intervals keeps the default schedule of the others
+    fn fill_defaults(&mut self) {
+        for (name, cron) in INTERVALS {
+            self.0
+                .entry(name.to_string())
+                .or_insert_with(|| cron.to_string());
+        }
+    }
+}
+
+/// Every problem found, so a bad config is fixed in one go
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Invalid configuration:")?;
+        for problem in self.0.iter() {
+            writeln!(f, "  - {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    /// Only the commands talking to Telegram need its token and moderator
+    pub fn load(needs_telegram: bool) -> Result<Config, ConfigError> {
+        let (path, explicit) = match env::var("CONFIG_PATH") {
+            Ok(path) => (path, true),
+            Err(_) => (DEFAULT_CONFIG_PATH.to_string(), false),
+        };
+        let mut config = if Path::new(&path).exists() {
+            let content = std::fs::read_to_string(&path)
+                .map_err(|e| ConfigError(vec![format!("cannot read {}: {}", path, e)]))?;
+            toml::from_str::<Config>(&content)
+                .map_err(|e| ConfigError(vec![format!("cannot parse {}: {}", path, e)]))?
+        } else if explicit {
+            return Err(ConfigError(vec![format!("{} does not exist", path)]));
+        } else {
+            Config::default()
+        };
+
+        config.intervals.fill_defaults();
+        let mut problems = config.apply_env();
+        if needs_telegram {
+            problems.extend(config.validate_telegram());
+        }
+        problems.extend(config.validate());
+        if problems.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(problems))
+        }
+    }
+
+    fn apply_env(&mut self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if let Ok(token) = env::var("TELOXIDE_TOKEN") {
+            self.telegram.token = token;
+        }
+        if let Ok(id) = env::var("MODERATOR_ID") {
+            match id.trim().parse() {
+                Ok(id) => self.telegram.moderator_id = id,
+                Err(_) => problems.push(format!("MODERATOR_ID '{}' is not a user id", id)),
+            }
+        }
+        if let Ok(ids) = env::var("OPERATOR_IDS") {
+            self.telegram.operator_ids.clear();
+            for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+                match id.parse() {
+                    Ok(id) => self.telegram.operator_ids.push(id),
+                    Err(_) => {
+                        problems.push(format!("OPERATOR_IDS entry '{}' is not a user id", id))
+                    }
+                }
+            }
+        }
+        if let Ok(url) = env::var("DATABASE_URL") {
+            self.database.url = url;
+        }
+        if let Ok(addr) = env::var("HTTP_ADDR") {
+            self.http.addr = addr;
+        }
+        if let Ok(url) = env::var("HYPERLIQUID_INFO_URL") {
+            self.hyperliquid.info_url = url;
+        }
+        if let Ok(link) = env::var("REFERRAL_LINK") {
+            self.links.referral = link;
+        }
+
+        if self.telegram.operator_ids.is_empty() && self.telegram.moderator_id != 0 {
+            self.telegram.operator_ids.push(self.telegram.moderator_id);
+        }
+        problems
+    }
+
+    fn validate_telegram(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if !self.telegram.token.contains(':') {
+            problems.push("telegram.token (TELOXIDE_TOKEN) is missing or malformed".to_string());
+        }
+        if self.telegram.moderator_id == 0 {
+            problems.push("telegram.moderator_id (MODERATOR_ID) is not set".to_string());
+        }
+        problems
+    }
+
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+
+        if self.database.url.is_empty() {
+            problems.push("database.url (DATABASE_URL) is not set".to_string());
+        }
+        if self.http.addr.parse::<std::net::SocketAddr>().is_err() {
+            problems.push(format!(
+                "http.addr '{}' is not a socket address",
+                self.http.addr
+            ));
+        }
+        if !self.hyperliquid.info_url.starts_with("http") {
+            problems.push(format!(
+                "hyperliquid.info_url '{}' is not an http(s) URL",
+                self.hyperliquid.info_url
+            ));
+        }
+        if !self.links.referral.starts_with("https://") {
+            problems.push(format!(
+                "links.referral '{}' is not an https URL",
+                self.links.referral
+            ));
+        }
+
+        for (name, value) in [
+            ("pump.special_percentage", self.pump.special_percentage),
+            (
+                "pump.over_special_percentage",
+                self.pump.over_special_percentage,
+            ),
+            ("pump.min_market_cap", self.pump.min_market_cap),
+            (
+                "thresholds.depth_range_percent",
+                self.thresholds.depth_range_percent,
+            ),
+            (
+                "thresholds.large_transfer_usd",
+                self.thresholds.large_transfer_usd,
+            ),
+            (
+                "thresholds.min_holding_change_percent",
+                self.thresholds.min_holding_change_percent,
+            ),
+        ] {
+            if !value.is_finite() || value <= 0.0 {
+                problems.push(format!("{} must be above 0, got {}", name, value));
+            }
+        }
+        for (name, value) in [
+            (
+                "quotas.max_demands_per_chat",
+                self.quotas.max_demands_per_chat as i64,
+            ),
+            (
+                "quotas.max_fills_per_message",
+                self.quotas.max_fills_per_message as i64,
+            ),
+            (
+                "quotas.max_supply_fetch_per_run",
+                self.quotas.max_supply_fetch_per_run as i64,
+            ),
+            (
+                "rate_limits.broadcast_concurrency",
+                self.rate_limits.broadcast_concurrency as i64,
+            ),
+            (
+                "rate_limits.error_window_secs",
+                self.rate_limits.error_window_secs,
+            ),
+            (
+                "permissions.admin_cache_ttl_secs",
+                self.permissions.admin_cache_ttl_secs,
+            ),
+        ] {
+            if value <= 0 {
+                problems.push(format!("{} must be above 0, got {}", name, value));
+            }
+        }
+
+        for chat_id in self.permissions.manager_ids.keys() {
+            if chat_id.parse::<i64>().is_err() {
+                problems.push(format!(
+                    "permissions.manager_ids key '{}' is not a chat id",
+                    chat_id
+                ));
+            }
+        }
+
+        // Names are stored in the demands, only the schedules can change
+        for (name, cron) in self.intervals.iter() {
+            if !INTERVALS.iter().any(|(known, _)| *known == name) {
+                problems.push(format!("intervals.{} is not a known interval", name));
+            }
+            if Schedule::from_str(cron).is_err() {
+                problems.push(format!(
+                    "intervals.{} '{}' is not a cron expression",
+                    name, cron
+                ));
+            }
+        }
+
+        // The main sequence runs on the 15min schedule, the others are only seen when it runs
+        if let Some(main) = self
+            .intervals
+            .cron(INTERVAL_15MIN)
+            .and_then(|cron| Schedule::from_str(cron).ok())
+        {
+            for (name, cron) in self.intervals.iter() {
+                let Ok(schedule) = Schedule::from_str(cron) else {
+                    continue;
+                };
+                if name != INTERVAL_15MIN && !fires_within(&schedule, &main) {
+                    problems.push(format!(
+                        "intervals.{} '{}' fires outside of the 15min schedule",
+                        name, cron
+                    ));
+                }
+            }
+        }
+        problems
+    }
+}
+
+/// Every firing of `schedule` is one of `main`, checked from a fixed date
+fn fires_within(schedule: &Schedule, main: &Schedule) -> bool {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    schedule
+        .after(&start)
+        .take(ALIGNMENT_CHECKS)
+        .all(|fired| main.after(&(fired - Duration::seconds(1))).next() == Some(fired))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn valid_config() -> Config {
+        let mut config = Config::default();
+        config.telegram.token = "1:test".to_string();
+        config.telegram.moderator_id = 42;
+        config.database.url = "postgres://localhost/wagmi".to_string();
+        config
+    }
+
+    #[test]
+    fn default_intervals_are_aligned() {
+        assert!(valid_config().validate().is_empty());
+    }
+
+    #[test]
+    fn interval_off_the_main_schedule_is_rejected() {
+        let mut config = valid_config();
+        config
+            .intervals
+            .0
+            .insert("1h".to_string(), "0 7 * * * *".to_string());
+        let problems = config.validate();
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].starts_with("intervals.1h"));
+    }
+
+    #[test]
+    fn interval_with_seconds_off_the_main_schedule_is_rejected() {
+        let mut config = valid_config();
+        config
+            .intervals
+            .0
+            .insert("6h".to_string(), "30 0 */6 * * *".to_string());
+        let problems = config.validate();
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].starts_with("intervals.6h"));
+    }
+}
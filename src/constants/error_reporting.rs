@@ -0,0 +1,3 @@
+pub const ERROR_WINDOW_SECS: i64 = 60 * 60; // a kind is notified at most once per window
+pub const ERROR_GROUP_TTL_SECS: i64 = 60 * 60 * 24; // idle kinds are forgotten after
+pub const ERROR_KIND_MAX_LEN: usize = 80;
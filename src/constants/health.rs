@@ -0,0 +1,4 @@
+pub const HEARTBEAT_MAX_AGE_SECS: i64 = 60; // heartbeat job runs every 15 seconds
+pub const FETCH_MAX_AGE_SECS: i64 = 20 * 60; // one main sequence every 15 minutes, with slack
+pub const RUN_MAX_AGE_SECS: i64 = 20 * 60;
+pub const DB_CHECK_TIMEOUT_SECS: u64 = 3;
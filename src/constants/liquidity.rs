@@ -0,0 +1,2 @@
+pub const DEPTH_RANGE_PERCENT: f64 = 2.0;
+pub const DEPTH_UNIT_USD: f64 = 1_000.0; // depth demands are stored in k$
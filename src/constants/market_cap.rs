@@ -0,0 +1,4 @@
+pub const SUPPLY_REFRESH_SECS: i64 = 60 * 60 * 24;
+pub const MAX_SUPPLY_FETCH_PER_RUN: usize = 20; // tokenDetails is rate limited, fill the cache progressively
+pub const MCAP_MIN_USD: f64 = 1_000.0;
+pub const MCAP_MAX_USD: f64 = 10_000_000_000_000.0;
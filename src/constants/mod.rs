@@ -1,2 +1,8 @@
+pub mod error_reporting;
+pub mod health;
+pub mod liquidity;
+pub mod market_cap;
+pub mod permissions;
 pub mod pumpcheck;
 pub mod schedules;
+pub mod wallet_watch;
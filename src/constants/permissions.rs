@@ -0,0 +1 @@
+pub const ADMIN_CACHE_TTL_SECS: i64 = 5 * 60; // chat_member updates invalidate it sooner
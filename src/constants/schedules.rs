@@ -16,6 +16,10 @@ pub const CRON_WEDNESDAY: &str = "0 0 12 * * Wed"; // Wednesday at noon
 pub const CRON_FRIDAY: &str = "0 0 12 * * Fri"; // Friday at noon
 pub const CRON_MONDAY: &str = "0 0 12 * * Mon"; // Monday at noon
 pub const CRON_SATURDAY: &str = "0 0 12 * * Sat"; // Saturday at noon
+pub const CRON_WALLET_WATCH: &str = "30 * * * * *"; // Every minute, off the main sequence second
+pub const CRON_HEARTBEAT: &str = "*/15 * * * * *"; // Every 15 seconds, scheduler liveness
+pub const CRON_ERROR_SUMMARY: &str = "0 5 * * * *"; // Every hour, after the main sequence
+pub const CRON_MATCH_TOLERANCE_SECS: i64 = 60; // Lateness of the main sequence still matching its intervals
 
 // All valid intervals with their cron expressions
 pub static INTERVALS: &[(&str, &str)] = &[
@@ -0,0 +1,3 @@
+pub const LARGE_TRANSFER_USD: f64 = 10_000.0;
+pub const MIN_HOLDING_CHANGE_PERCENT: f64 = 5.0;
+pub const MAX_FILLS_PER_MESSAGE: usize = 10;
@@ -0,0 +1,84 @@
+// src/context.rs
+
+use anyhow::anyhow;
+use sqlx::{Pool, Postgres};
+use std::sync::Arc;
+use teloxide::{prelude::Bot, types::UserId};
+
+use crate::{
+    bot::messenger::{Messenger, TelegramMessenger},
+    clock::{Clock, SystemClock},
+    config::Config,
+    init::init_pool,
+    state::{ChatDemands, ErrorGroups, MarketState, PermissionCache, RuntimeStatus},
+};
+
+/// Everything the handlers and the scheduled jobs share.
+/// Built once in `main`, given to dptree as a dependency and captured by each job.
+/// Cloning only clones the handles.
+#[derive(Clone)]
+pub struct AppContext {
+    pub config: Arc<Config>,
+    pub bot: Bot,
+    pub messenger: Arc<dyn Messenger>, // every message sent goes through it
+    pub clock: Arc<dyn Clock>,
+    pub bot_id: UserId,
+    pub pool: Pool<Postgres>,
+    pub market: Arc<MarketState>,
+    pub chat_demands: Arc<ChatDemands>,
+    pub permissions: Arc<PermissionCache>,
+    pub status: Arc<RuntimeStatus>,
+    pub error_groups: Arc<ErrorGroups>,
+}
+
+impl AppContext {
+    pub async fn new(config: Config) -> anyhow::Result<Self> {
+        let bot = Bot::new(&config.telegram.token);
+        let pool = init_pool(&config.database.url).await?;
+        let messenger = Arc::new(TelegramMessenger::new(bot.clone()));
+        Self::from_parts(config, bot, pool, messenger, Arc::new(SystemClock))
+    }
+
+    /// Same as `new` with the connections and the clock given,
+    /// the tests record the messages and drive the time instead
+    pub fn from_parts(
+        config: Config,
+        bot: Bot,
+        pool: Pool<Postgres>,
+        messenger: Arc<dyn Messenger>,
+        clock: Arc<dyn Clock>,
+    ) -> anyhow::Result<Self> {
+        let bot_id = config
+            .telegram
+            .token
+            .split(':')
+            .next()
+            .and_then(|id| id.parse().ok())
+            .map(UserId)
+            .ok_or_else(|| anyhow!("Could not read the bot id from the token"))?;
+
+        let status = RuntimeStatus::new(clock.now().timestamp());
+        Ok(Self {
+            config: Arc::new(config),
+            bot,
+            messenger,
+            clock,
+            bot_id,
+            pool,
+            market: Arc::default(),
+            chat_demands: Arc::default(),
+            permissions: Arc::default(),
+            status: Arc::new(status),
+            error_groups: Arc::default(),
+        })
+    }
+
+    pub fn is_operator(&self, user_id: UserId) -> bool {
+        self.config.telegram.operator_ids.contains(&user_id.0)
+    }
+
+    /// The pair number is appended to it
+    pub fn referral_link(&self) -> &str {
+        &self.config.links.referral
+    }
+}
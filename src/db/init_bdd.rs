@@ -1,126 +1,168 @@
-use crate::global_data::get_pool;
-use sqlx::postgres::PgPool;
-use std::error::Error;
-use std::ops::Deref;
-use std::path::Path;
-
-#[derive(Debug)]
-pub enum DatabaseError {
-    ConnectionError(String),
-    SchemaError(String),
-    SqlxError(sqlx::Error),
-}
-
-impl From<sqlx::Error> for DatabaseError {
-    fn from(err: sqlx::Error) -> Self {
-        DatabaseError::SqlxError(err)
-    }
-}
-
-pub async fn check_and_init_db() -> Result<(), DatabaseError> {
-    let pool = get_pool();
-
-    // Check if our tables exist by looking for the 'chat' table
-    let table_exists = sqlx::query_scalar!(
-        r#"
-        SELECT EXISTS (
-            SELECT FROM information_schema.tables 
-            WHERE table_schema = 'public' 
-            AND table_name = 'chat'
-        ) as "exists!"
-        "#
+use anyhow::anyhow;
+use chrono::Utc;
+use sqlx::{Executor, Pool, Postgres, Row};
+
+use crate::{
+    db::services::universe::{fetch_known_tokens, save_known_universe},
+    hyperliquid::fetch_price::fetch_token_data,
+    procedures::listing_alert::known_from_universe,
+};
+
+const SCHEMA: &str = include_str!("sql/bdd.sql");
+
+/// Applied in order, once each, recorded in `schema_migrations`
+const MIGRATIONS: &[(&str, &str)] = &[
+    (
+        "001_known_universe",
+        include_str!("sql/migrations/001_known_universe.sql"),
+    ),
+    (
+        "002_watched_wallets",
+        include_str!("sql/migrations/002_watched_wallets.sql"),
+    ),
+    (
+        "003_perp_snapshots",
+        include_str!("sql/migrations/003_perp_snapshots.sql"),
+    ),
+    (
+        "004_demand_threshold_usd",
+        include_str!("sql/migrations/004_demand_threshold_usd.sql"),
+    ),
+    (
+        "005_token_identity",
+        include_str!("sql/migrations/005_token_identity.sql"),
+    ),
+    (
+        "006_error_references",
+        include_str!("sql/migrations/006_error_references.sql"),
+    ),
+    (
+        "007_chat_managers",
+        include_str!("sql/migrations/007_chat_managers.sql"),
+    ),
+    (
+        "008_chat_public_commands",
+        include_str!("sql/migrations/008_chat_public_commands.sql"),
+    ),
+];
+
+/// Resolves token names through `known_tokens`, see `seed_known_universe`
+const TOKEN_IDENTITY_MIGRATION: &str = "005_token_identity";
+
+/// Creates the tables on an empty database, otherwise applies the migrations not applied yet.
+/// `bdd.sql` is the current schema, a new database records every migration as applied.
+/// Returns what was done.
+pub async fn check_and_init_db(
+    pool: &Pool<Postgres>,
+    info_url: &str,
+) -> anyhow::Result<Vec<String>> {
+    pool.execute(
+        "CREATE TABLE IF NOT EXISTS schema_migrations (
+            name VARCHAR PRIMARY KEY,
+            applied_at BIGINT NOT NULL
+        )",
     )
-    .fetch_one(pool.deref())
-    .await?
-    .expect("Failed to check table existence");
+    .await?;
 
-    if !table_exists {
-        println!("Initializing database schema...");
+    // Looked up through the search path, not only in `public`
+    let table_exists: bool = sqlx::query("SELECT to_regclass('chat') IS NOT NULL")
+        .fetch_one(pool)
+        .await?
+        .get(0);
 
-        // Read the SQL schema file
-        let schema_path = "src/db/sql/bdd.sql";
-        if !Path::new(schema_path).exists() {
-            return Err(DatabaseError::SchemaError(format!(
-                "Schema file not found at: {}",
-                schema_path
-            )));
-        }
-
-        let schema = std::fs::read_to_string(schema_path)
-            .map_err(|e| DatabaseError::SchemaError(e.to_string()))?;
-
-        // Execute schema in a transaction
+    let mut done = Vec::new();
+    if !table_exists {
         let mut tx = pool.begin().await?;
-
-        // Split and execute schema (handling potential dollar-quoted blocks)
-        for statement in split_sql_statements(&schema) {
-            if !statement.trim().is_empty() {
-                sqlx::query(&statement)
-                    .execute(&mut *tx)
-                    .await
-                    .map_err(|e| DatabaseError::SqlxError(e))?;
-            }
+        tx.execute(SCHEMA)
+            .await
+            .map_err(|e| anyhow!("Schema creation failed: {}", e))?;
+        for (name, _) in MIGRATIONS {
+            record_migration(&mut tx, name).await?;
         }
-
-        // Commit the transaction
         tx.commit().await?;
-
-        println!("Database schema initialized successfully");
-    } else {
-        println!("Database schema already exists");
+        done.push("schema created".to_owned());
+        return Ok(done);
     }
 
-    Ok(())
-}
-
-// Helper function to split SQL statements while preserving dollar-quoted blocks
-fn split_sql_statements(sql: &str) -> Vec<String> {
-    let mut statements = Vec::new();
-    let mut current_statement = String::new();
-    let mut in_dollar_quote = false;
-    let mut dollar_quote_tag = String::new();
-
-    for line in sql.lines() {
-        let trimmed = line.trim();
-
-        // Skip empty lines and comments
-        if trimmed.is_empty() || trimmed.starts_with("--") {
+    let applied: Vec<String> = sqlx::query("SELECT name FROM schema_migrations")
+        .fetch_all(pool)
+        .await?
+        .iter()
+        .map(|row| row.get(0))
+        .collect();
+    for (name, sql) in MIGRATIONS {
+        if applied.iter().any(|applied| applied == name) {
             continue;
         }
-
-        if !in_dollar_quote {
-            // Check for dollar quote start
-            if let Some(pos) = trimmed.find("$$") {
-                in_dollar_quote = true;
-                dollar_quote_tag = trimmed[..pos].to_string();
-            }
-
-            // Add line to current statement
-            current_statement.push_str(line);
-            current_statement.push('\n');
-
-            // If not in dollar quote and line ends with semicolon, split statement
-            if !in_dollar_quote && trimmed.ends_with(';') {
-                statements.push(current_statement.clone());
-                current_statement.clear();
-            }
-        } else {
-            // Add line to current statement
-            current_statement.push_str(line);
-            current_statement.push('\n');
-
-            // Check for matching dollar quote end
-            if trimmed.ends_with(&format!("$$")) {
-                in_dollar_quote = false;
-                dollar_quote_tag.clear();
-            }
+        if *name == TOKEN_IDENTITY_MIGRATION && seed_known_universe(pool, info_url).await? {
+            done.push("known universe seeded".to_owned());
+        }
+        let mut tx = pool.begin().await?;
+        tx.execute(*sql)
+            .await
+            .map_err(|e| anyhow!("Migration {} failed: {}", name, e))?;
+        record_migration(&mut tx, name).await?;
+        tx.commit().await?;
+        done.push(format!("{} applied", name));
+        if *name == TOKEN_IDENTITY_MIGRATION {
+            done.extend(unresolved_spot_demands(pool).await?);
         }
     }
+    Ok(done)
+}
+
+/// Spot demands still keyed by a name no known token has, they never match again
+async fn unresolved_spot_demands(pool: &Pool<Postgres>) -> anyhow::Result<Vec<String>> {
+    let rows = sqlx::query(
+        "SELECT chat_id, type_of, token FROM demands
+         WHERE type_of IN ('alert', 'spread', 'depth', 'mcap') AND token !~ '^[0-9]+$'
+         ORDER BY chat_id, type_of, token",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let unresolved = format!(
+                "unresolved {} demand on '{}' in chat {}, delete it and set it again",
+                row.get::<String, _>("type_of"),
+                row.get::<String, _>("token"),
+                row.get::<i64, _>("chat_id")
+            );
+            warn!("{}", unresolved);
+            unresolved
+        })
+        .collect())
+}
 
-    // Add any remaining statement
-    if !current_statement.trim().is_empty() {
-        statements.push(current_statement);
+/// A database upgraded without running the bot since `001_known_universe` has no known
+/// tokens, the names of its demands would stay unresolved. Returns whether it seeded.
+async fn seed_known_universe(pool: &Pool<Postgres>, info_url: &str) -> anyhow::Result<bool> {
+    if !fetch_known_tokens(pool).await?.is_empty() {
+        return Ok(false);
+    }
+    let (_, universe, report) = fetch_token_data(info_url)
+        .await
+        .map_err(|e| anyhow!("Cannot fetch the spot universe: {}", e))?;
+    if !universe.complete {
+        return Err(anyhow!(
+            "The spot universe fetch skipped {} items, retry the migration",
+            report.failed.len()
+        ));
     }
+    let (tokens, pairs) = known_from_universe(&universe);
+    save_known_universe(pool, &tokens, &pairs).await?;
+    Ok(true)
+}
 
-    statements
+async fn record_migration(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    name: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("INSERT INTO schema_migrations (name, applied_at) VALUES ($1, $2)")
+        .bind(name)
+        .bind(Utc::now().timestamp())
+        .execute(tx)
+        .await?;
+    Ok(())
 }
@@ -1,3 +1,3 @@
 // pub mod diesel;
-// pub mod init_bdd;
+pub mod init_bdd;
 pub mod services;
@@ -1,16 +1,15 @@
-use crate::global_data::get_pool;
+use crate::metrics::db_timer;
 use anyhow::anyhow;
 use sqlx::{Pool, Postgres, Row};
 use std::collections::HashMap;
-use std::sync::Arc;
 
-pub async fn insert_chat(chat_id_no: i64) -> anyhow::Result<()> {
-    let pool: Arc<Pool<Postgres>> = get_pool();
+pub async fn insert_chat(pool: &Pool<Postgres>, chat_id_no: i64) -> anyhow::Result<()> {
+    let _timer = db_timer("insert_chat");
     debug!("Inserting chat with id {:?}", chat_id_no);
 
     sqlx::query("INSERT INTO chat (id) VALUES ($1) ON CONFLICT (id) DO NOTHING")
         .bind(chat_id_no)
-        .execute(pool.as_ref())
+        .execute(pool)
         .await
         .map_err(|e| {
             error!("Error while inserting chat: {:?}", e);
@@ -20,12 +19,56 @@ pub async fn insert_chat(chat_id_no: i64) -> anyhow::Result<()> {
     Ok(())
 }
 
-pub async fn fetch_chat_demand_counts() -> anyhow::Result<HashMap<i64, u8>> {
-    let pool: Arc<Pool<Postgres>> = get_pool();
+/// False for a chat the bot doesn't know yet
+pub async fn fetch_public_commands(pool: &Pool<Postgres>, chat_id: i64) -> anyhow::Result<bool> {
+    let _timer = db_timer("fetch_public_commands");
+
+    let row = sqlx::query("SELECT public_commands FROM chat WHERE id = $1")
+        .bind(chat_id)
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| anyhow!("Error while getting chat {}: {:?}", chat_id, e))?;
+
+    Ok(row.is_some_and(|row| row.get(0)))
+}
+
+pub async fn set_public_commands(
+    pool: &Pool<Postgres>,
+    chat_id: i64,
+    public_commands: bool,
+) -> anyhow::Result<()> {
+    let _timer = db_timer("set_public_commands");
+
+    sqlx::query(
+        "INSERT INTO chat (id, public_commands) VALUES ($1, $2)
+         ON CONFLICT (id) DO UPDATE SET public_commands = $2",
+    )
+    .bind(chat_id)
+    .bind(public_commands)
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Error while updating chat {}: {:?}", chat_id, e))?;
+
+    Ok(())
+}
+
+pub async fn fetch_all_chat_ids(pool: &Pool<Postgres>) -> anyhow::Result<Vec<i64>> {
+    let _timer = db_timer("fetch_all_chat_ids");
+
+    let rows = sqlx::query("SELECT id FROM chat ORDER BY id")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("Error while getting chats: {:?}", e))?;
+
+    Ok(rows.into_iter().map(|row| row.get(0)).collect())
+}
+
+pub async fn fetch_chat_demand_counts(pool: &Pool<Postgres>) -> anyhow::Result<HashMap<i64, u8>> {
+    let _timer = db_timer("fetch_chat_demand_counts");
 
     let rows =
         sqlx::query("SELECT chat_id, COUNT(*) AS demand_count FROM demands GROUP BY chat_id")
-            .fetch_all(pool.as_ref())
+            .fetch_all(pool)
             .await
             .map_err(|e| {
                 let e_str = format!("Error while getting chat demand counts: {:?}", e);
@@ -1,19 +1,26 @@
-use sqlx::Row;
+use serde::{Deserialize, Serialize};
+use sqlx::{Pool, Postgres, Row};
 use std::collections::HashMap;
 
 use base64::{engine::general_purpose::STANDARD as base64, Engine as _};
-use std::ops::Deref;
+use sha2::{Digest, Sha256};
 use teloxide::types::{ChatId, InlineKeyboardButton, InlineKeyboardMarkup, ThreadId};
 
+use crate::bot::utils::{escape_user_text, format_usd_compact};
 use crate::bot::{send_error_to_moderator, send_message, send_message_with_button};
-use crate::global_data::{decrease_chat_demand, get_pool, increase_chat_demand};
+use crate::context::AppContext;
+use crate::error::{BotError, BotResult};
+use crate::metrics::db_timer;
+use crate::state::TokenMapping;
 use crate::{
     db::services::chat::insert_chat,
-    global_data::CHAT_DEMAND_MAP,
-    types::commands::{ALERT, SPECIAL},
+    types::commands::{
+        ALERT, DEPTH, FUNDING_ABOVE, FUNDING_BELOW, FUNDING_FLIP, LISTING, MCAP, OI_CHANGE,
+        SPECIAL, SPREAD, WATCH,
+    },
 };
 
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct Demand {
     pub chat_id: i64,
     pub thread_id: Option<i32>,
@@ -21,6 +28,9 @@ pub struct Demand {
     pub token: String,
     pub percentage: i16,
     pub interval: String,
+    #[serde(default)]
+    pub threshold_usd: i64, // market cap level, 0 for the other types
+    pub label: Option<String>,
 }
 
 impl Demand {
@@ -34,18 +44,31 @@ impl Demand {
     }
     pub fn get_composite_id(&self) -> String {
         let composite = format!(
-            "{}_{}_{}_{}_{}",
-            self.chat_id, self.type_of, self.token, self.percentage, self.interval
+            "{}_{}_{}_{}_{}_{}",
+            self.chat_id,
+            self.type_of,
+            self.token,
+            self.percentage,
+            self.interval,
+            self.threshold_usd
         );
         base64.encode(composite.as_bytes())
     }
 
-    pub fn parse_composite_id(id: &str) -> Option<(i64, String, String, i16, String)> {
+    /// Fits the 64 bytes of a button's data, the demand is looked up among its chat's
+    pub fn get_button_key(&self) -> String {
+        Sha256::digest(self.get_composite_id().as_bytes())[..8]
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    pub fn parse_composite_id(id: &str) -> Option<(i64, String, String, i16, String, i64)> {
         let bytes = base64.decode(id).ok()?;
         let composite = String::from_utf8(bytes).ok()?;
 
         let parts: Vec<&str> = composite.split('_').collect();
-        if parts.len() != 5 {
+        if parts.len() != 6 {
             return None;
         }
 
@@ -54,21 +77,22 @@ impl Demand {
         let token = parts[2].to_string();
         let percentage = parts[3].parse().ok()?;
         let interval = parts[4].to_string();
+        let threshold_usd = parts[5].parse().ok()?;
 
-        Some((chat_id, type_of, token, percentage, interval))
+        Some((chat_id, type_of, token, percentage, interval, threshold_usd))
     }
-    pub async fn delete_demand(self) -> anyhow::Result<()> {
-        delete_demand_by_composite_id(&self.get_composite_id()).await
+    pub async fn delete_demand(self, ctx: &AppContext) -> BotResult<()> {
+        delete_demand_by_composite_id(ctx, &self.get_composite_id()).await
     }
 
-    pub async fn insert_to_db(self) -> anyhow::Result<()> {
-        let pool = get_pool();
-        let _ = insert_chat(self.chat_id).await;
+    pub async fn insert_to_db(self, ctx: &AppContext) -> BotResult<()> {
+        let _timer = db_timer("insert_demand");
+        let _ = insert_chat(&ctx.pool, self.chat_id).await;
 
         // First do the DB insert
         sqlx::query(
-            "INSERT INTO demands (chat_id, thread_id, type_of, token, percentage, interval)
-         VALUES ($1, $2, $3, $4, $5, $6)",
+            "INSERT INTO demands (chat_id, thread_id, type_of, token, percentage, interval, threshold_usd, label)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
         )
         .bind(self.chat_id)
         .bind(self.thread_id)
@@ -76,19 +100,21 @@ impl Demand {
         .bind(&self.token)
         .bind(self.percentage)
         .bind(&self.interval)
-        .execute(pool.deref())
+        .bind(self.threshold_usd)
+        .bind(&self.label)
+        .execute(&ctx.pool)
         .await
         .map_err(|e| {
             if let Some(db_error) = e.as_database_error() {
                 if db_error.code().as_deref() == Some("23505") {
-                    return anyhow::anyhow!("Demand already exists");
+                    return BotError::user_input("Demand already exists");
                 }
             }
-            anyhow::anyhow!("Failed to insert demand: {}", e)
+            BotError::Database(anyhow::anyhow!("Failed to insert demand: {}", e))
         })?;
 
         // Then update the map in a separate block to ensure lock is held
-        increase_chat_demand(self.chat_id).await;
+        ctx.chat_demands.increase(self.chat_id).await;
 
         Ok(())
     }
@@ -97,95 +123,175 @@ impl Demand {
 
 // Database operations implementation
 
-pub async fn delete_demand_by_composite_id(composite_id: &str) -> anyhow::Result<()> {
-    if let Some((chat_id, type_of, token, percentage, interval)) =
+pub async fn delete_demand_by_composite_id(ctx: &AppContext, composite_id: &str) -> BotResult<()> {
+    if let Some((chat_id, type_of, token, percentage, interval, threshold_usd)) =
         Demand::parse_composite_id(composite_id)
     {
-        let pool = get_pool();
-        sqlx::query(
+        let _timer = db_timer("delete_demand_by_composite_id");
+        let result = sqlx::query(
             "DELETE FROM demands 
              WHERE chat_id = $1 
              AND type_of = $2 
              AND token = $3 
              AND percentage = $4 
-             AND interval = $5",
+             AND interval = $5
+             AND threshold_usd = $6",
         )
         .bind(chat_id)
         .bind(type_of)
         .bind(token)
         .bind(percentage)
         .bind(interval)
-        .execute(pool.deref())
+        .bind(threshold_usd)
+        .execute(&ctx.pool)
         .await
-        .map_err(|e| anyhow::anyhow!("Failed to delete demand: {}", e))?;
+        .map_err(|e| BotError::Database(anyhow::anyhow!("Failed to delete demand: {}", e)))?;
+        // Pressed twice, or deleted since the list was sent
+        if result.rows_affected() == 0 {
+            return Err(BotError::user_input("This alert isn't set anymore"));
+        }
 
-        decrease_chat_demand(chat_id).await;
+        ctx.chat_demands.decrease(chat_id).await;
         Ok(())
     } else {
-        Err(anyhow::anyhow!("Invalid composite ID format"))
+        Err(BotError::user_input("Invalid composite ID format"))
     }
 }
-pub async fn delete_demands_for_chat(chat_id_param: i64) -> anyhow::Result<()> {
-    let pool = get_pool();
+pub async fn delete_demands_for_chat(ctx: &AppContext, chat_id_param: i64) -> anyhow::Result<()> {
+    let _timer = db_timer("delete_demands_for_chat");
 
     sqlx::query("DELETE FROM demands WHERE chat_id = $1")
         .bind(chat_id_param)
-        .execute(pool.deref())
+        .execute(&ctx.pool)
         .await
         .map_err(|e| anyhow::anyhow!("Failed to delete demands: {}", e))?;
 
-    CHAT_DEMAND_MAP.lock().await.remove(&chat_id_param);
+    ctx.chat_demands.remove(chat_id_param).await;
     Ok(())
 }
 
-pub async fn get_all_special_chat_id() -> anyhow::Result<Vec<i64>> {
-    let pool = get_pool();
+pub async fn get_all_special_chat_id(pool: &Pool<Postgres>) -> anyhow::Result<Vec<i64>> {
+    get_all_chat_id_for_type(pool, SPECIAL).await
+}
+
+pub async fn get_all_listing_chat_id(pool: &Pool<Postgres>) -> anyhow::Result<Vec<i64>> {
+    get_all_chat_id_for_type(pool, LISTING).await
+}
+
+async fn get_all_chat_id_for_type(
+    pool: &Pool<Postgres>,
+    type_of: &str,
+) -> anyhow::Result<Vec<i64>> {
+    let _timer = db_timer("get_all_chat_id_for_type");
 
     let rows = sqlx::query("SELECT chat_id FROM demands WHERE type_of = $1")
-        .bind(SPECIAL)
-        .fetch_all(pool.deref())
+        .bind(type_of)
+        .fetch_all(pool)
         .await
-        .map_err(|e| anyhow::anyhow!("Failed to get pump check chat ids: {}", e))?;
+        .map_err(|e| anyhow::anyhow!("Failed to get {} chat ids: {}", type_of, e))?;
 
     Ok(rows.into_iter().map(|row| row.get(0)).collect())
 }
 
-pub async fn fetch_last_regular_demands_by_time(time: &str) -> anyhow::Result<Vec<Demand>> {
-    let pool = get_pool();
+pub async fn fetch_last_regular_demands_by_time(
+    pool: &Pool<Postgres>,
+    time: &str,
+) -> anyhow::Result<Vec<Demand>> {
+    fetch_demands_by_time_and_type(pool, time, ALERT).await
+}
+
+pub async fn fetch_demands_by_time_and_type(
+    pool: &Pool<Postgres>,
+    time: &str,
+    type_of: &str,
+) -> anyhow::Result<Vec<Demand>> {
+    let _timer = db_timer("fetch_demands_by_time_and_type");
 
     let rows = sqlx::query(
-        "SELECT chat_id, thread_id, type_of, token, percentage, interval
+        "SELECT chat_id, thread_id, type_of, token, percentage, interval, threshold_usd, label
          FROM demands
          WHERE interval = $1 AND type_of = $2",
     )
     .bind(time)
-    .bind(ALERT)
-    .fetch_all(pool.deref())
+    .bind(type_of)
+    .fetch_all(pool)
     .await
     .map_err(|e| anyhow::anyhow!("Error fetching demands: {}", e))?;
 
-    let demands = rows
-        .into_iter()
-        .map(|row| Demand {
-            chat_id: row.get("chat_id"),
-            thread_id: row.get("thread_id"),
-            type_of: row.get("type_of"),
-            token: row.get("token"),
-            percentage: row.get("percentage"),
-            interval: row.get("interval"),
-        })
-        .collect();
+    let demands = rows.iter().map(demand_from_row).collect();
 
     Ok(demands)
 }
 
+pub async fn get_demands_by_type(
+    pool: &Pool<Postgres>,
+    type_of: &str,
+) -> anyhow::Result<Vec<Demand>> {
+    let _timer = db_timer("get_demands_by_type");
+
+    let rows = sqlx::query(
+        "SELECT chat_id, thread_id, type_of, token, percentage, interval, threshold_usd, label
+         FROM demands
+         WHERE type_of = $1",
+    )
+    .bind(type_of)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow::anyhow!("Error fetching {} demands: {}", type_of, e))?;
+
+    let demands = rows.iter().map(demand_from_row).collect();
+
+    Ok(demands)
+}
+
+/// (type_of, interval, count), for the metrics
+pub async fn count_demands_by_type_and_interval(
+    pool: &Pool<Postgres>,
+) -> anyhow::Result<Vec<(String, String, i64)>> {
+    let _timer = db_timer("count_demands_by_type_and_interval");
+
+    let rows = sqlx::query(
+        "SELECT type_of, interval, COUNT(*) AS demand_count
+         FROM demands
+         GROUP BY type_of, interval",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow::anyhow!("Error counting demands: {}", e))?;
+
+    Ok(rows
+        .iter()
+        .map(|row| {
+            (
+                row.get("type_of"),
+                row.get("interval"),
+                row.get("demand_count"),
+            )
+        })
+        .collect())
+}
+
+fn demand_from_row(row: &sqlx::postgres::PgRow) -> Demand {
+    Demand {
+        chat_id: row.get("chat_id"),
+        thread_id: row.get("thread_id"),
+        type_of: row.get("type_of"),
+        token: row.get("token"),
+        percentage: row.get("percentage"),
+        interval: row.get("interval"),
+        threshold_usd: row.get("threshold_usd"),
+        label: row.get("label"),
+    }
+}
+
 pub async fn batch_fetch_last_demands_by_time(
+    pool: &Pool<Postgres>,
     times: Vec<String>,
 ) -> anyhow::Result<HashMap<String, Vec<Demand>>> {
     let mut map = HashMap::new();
 
     for time in times {
-        match fetch_last_regular_demands_by_time(&time).await {
+        match fetch_last_regular_demands_by_time(pool, &time).await {
             Ok(demands) => {
                 map.insert(time.clone(), demands);
             }
@@ -201,71 +307,156 @@ pub async fn batch_fetch_last_demands_by_time(
     Ok(map)
 }
 
-pub async fn get_demands_by_chat_id(chat_id: i64) -> anyhow::Result<Vec<Demand>> {
-    let pool = get_pool();
+pub async fn get_demands_by_chat_id(
+    pool: &Pool<Postgres>,
+    chat_id: i64,
+) -> anyhow::Result<Vec<Demand>> {
+    let _timer = db_timer("get_demands_by_chat_id");
     let rows = sqlx::query(
-        "SELECT chat_id, thread_id, type_of, token, percentage, interval 
+        "SELECT chat_id, thread_id, type_of, token, percentage, interval, threshold_usd, label
          FROM demands 
          WHERE chat_id = $1 
-         ORDER BY type_of, token, percentage, interval", // ordered for consistency
+         ORDER BY type_of, token, percentage, interval, threshold_usd", // ordered for consistency
     )
     .bind(chat_id)
-    .fetch_all(pool.deref())
+    .fetch_all(pool)
     .await
     .map_err(|e| anyhow::anyhow!("Failed to fetch demands for chat_id {}: {}", chat_id, e))?;
 
-    let demands = rows
-        .into_iter()
-        .map(|row| Demand {
-            chat_id: row.get("chat_id"),
-            thread_id: row.get("thread_id"),
-            type_of: row.get("type_of"),
-            token: row.get("token"),
-            percentage: row.get("percentage"),
-            interval: row.get("interval"),
-        })
-        .collect();
+    let demands = rows.iter().map(demand_from_row).collect();
 
     Ok(demands)
 }
 
-pub fn send_demands_for(chat_id: ChatId, thread_id: Option<ThreadId>, demands: Vec<Demand>) {
-    if demands.len() == 0 {
-        return send_message(chat_id, "No alert sert for now", thread_id);
+/// Every chat, for the exports
+pub async fn fetch_all_demands(pool: &Pool<Postgres>) -> anyhow::Result<Vec<Demand>> {
+    let _timer = db_timer("fetch_all_demands");
+    let rows = sqlx::query(
+        "SELECT chat_id, thread_id, type_of, token, percentage, interval, threshold_usd, label
+         FROM demands
+         ORDER BY chat_id, type_of, token, percentage, interval, threshold_usd",
+    )
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow::anyhow!("Failed to fetch demands: {}", e))?;
+
+    Ok(rows.iter().map(demand_from_row).collect())
+}
+
+pub fn send_demands_for(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    demands: Vec<Demand>,
+    token_map: &TokenMapping,
+    deletable: bool, // with a delete button for each
+) {
+    if demands.is_empty() {
+        return send_message(ctx, chat_id, "No alert sert for now", thread_id);
     }
 
-    let mut message = format!("__*Here is your alerts*__:\n");
+    let mut message = String::from("__*Here is your alerts*__:\n");
     let mut keyboard: Vec<Vec<InlineKeyboardButton>> = Vec::new();
     for (i, demand) in demands.iter().enumerate() {
         message.push_str(&format!(
             "--------- \n__{i}__: {}\n",
-            format_demand_for_message(demand)
+            format_demand_for_message(ctx, demand, token_map)
         ));
         keyboard.push(vec![InlineKeyboardButton::callback(
-            &format!("{i}"),
-            &format!("{}_{}", chat_id.0, demand.get_composite_id()),
+            format!("{i}"),
+            format!("{}_{}", chat_id.0, demand.get_button_key()),
         )]);
     }
+    if !deletable {
+        return send_message(ctx, chat_id, &message, thread_id);
+    }
     message.push_str("\n*Chose which one you want to delete*:");
 
     let clavier = InlineKeyboardMarkup::new(keyboard);
-    send_message_with_button(chat_id, &message, thread_id, clavier);
+    send_message_with_button(ctx, chat_id, &message, thread_id, clavier);
+}
+
+/// Spot demands store the token index, show its name when still listed
+pub fn token_display_name(token: &str, token_map: &TokenMapping) -> String {
+    token_map
+        .get(token)
+        .map(|info| escape_user_text(&info.name))
+        .unwrap_or_else(|| format!("\\#{token}"))
 }
 
-pub fn format_demand_for_message(demands: &Demand) -> String {
+pub fn format_demand_for_message(
+    ctx: &AppContext,
+    demands: &Demand,
+    token_map: &TokenMapping,
+) -> String {
     let end_str = match demands.percentage {
         0 => String::new(),
         x => format!("for {x}%"),
     };
+    let spot_name = || token_display_name(&demands.token, token_map);
     match demands.type_of.as_str() {
         ALERT => format!(
             "*{}* price change {end_str} for {}",
+            spot_name(),
+            demands.interval
+        ),
+        SPECIAL => "*Special* demand".to_string(),
+        LISTING => "*New listing* demand".to_string(),
+        FUNDING_ABOVE => format!(
+            "*{}* funding above {}% annualized",
+            demands.token, demands.percentage
+        ),
+        FUNDING_BELOW => format!(
+            "*{}* funding below {}% annualized",
+            demands.token, demands.percentage
+        ),
+        FUNDING_FLIP => format!("*{}* funding sign flip", demands.token),
+        OI_CHANGE => format!(
+            "*{}* open interest change {end_str} for {}",
             demands.token, demands.interval
         ),
-        SPECIAL => format!("*Special* demand"),
+        SPREAD => format!("*{}* spread above {} bps", spot_name(), demands.percentage),
+        DEPTH => format!("*{}* bid depth below {}k$", spot_name(), demands.percentage),
+        MCAP => format!(
+            "*{}* market cap crossing {}",
+            spot_name(),
+            format_usd_compact(demands.threshold_usd as f64)
+        ),
+        WATCH => format!(
+            "*Watch* {} `{}`",
+            escape_user_text(demands.label.as_deref().unwrap_or_default()),
+            demands.token
+        ),
         _ => {
-            send_error_to_moderator(format!("demands.type_of {}", demands.type_of));
-            format!("Unexpected demand")
+            send_error_to_moderator(ctx, format!("demands.type_of {}", demands.type_of));
+            "Unexpected demand".to_string()
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn button_data_fits_telegram_limit() {
+        let demand = Demand {
+            token: "0x0123456789abcdef0123456789abcdef01234567".to_owned(),
+            label: Some("treasury".to_owned()),
+            ..Demand::new(-1001234567890, WATCH, Some(12))
+        };
+        let data = format!("{}_{}", demand.chat_id, demand.get_button_key());
+        assert!(data.len() <= 64, "{} bytes", data.len());
+    }
+
+    #[test]
+    fn button_key_differs_between_demands() {
+        let demand = |percentage| Demand {
+            token: "PURR".to_owned(),
+            percentage,
+            ..Demand::new(1, ALERT, None)
+        };
+        assert_eq!(demand(10).get_button_key(), demand(10).get_button_key());
+        assert_ne!(demand(10).get_button_key(), demand(20).get_button_key());
+    }
+}
@@ -0,0 +1,67 @@
+use anyhow::anyhow;
+use sqlx::{Pool, Postgres, Row};
+
+use crate::metrics::db_timer;
+
+#[derive(Debug, Clone)]
+pub struct ErrorReference {
+    pub reference: String,
+    pub user_id: i64,
+    pub message: String,
+    pub created_at: i64,
+}
+
+pub async fn insert_error_reference(
+    pool: &Pool<Postgres>,
+    error_ref: &ErrorReference,
+) -> anyhow::Result<()> {
+    let _timer = db_timer("insert_error_reference");
+
+    sqlx::query(
+        "INSERT INTO error_references (reference, user_id, message, created_at)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (reference) DO NOTHING",
+    )
+    .bind(&error_ref.reference)
+    .bind(error_ref.user_id)
+    .bind(&error_ref.message)
+    .bind(error_ref.created_at)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        anyhow!(
+            "Failed to store error reference {}: {}",
+            error_ref.reference,
+            e
+        )
+    })?;
+
+    Ok(())
+}
+
+pub async fn fetch_error_reference(
+    pool: &Pool<Postgres>,
+    reference: &str,
+) -> anyhow::Result<Option<ErrorReference>> {
+    let _timer = db_timer("fetch_error_reference");
+
+    let row = sqlx::query(
+        "SELECT reference, user_id, message, created_at
+         FROM error_references
+         WHERE reference = $1",
+    )
+    .bind(reference)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Failed to fetch error reference {}: {}", reference, e))?;
+
+    match row {
+        Some(row) => Ok(Some(ErrorReference {
+            reference: row.try_get("reference")?,
+            user_id: row.try_get("user_id")?,
+            message: row.try_get("message")?,
+            created_at: row.try_get("created_at")?,
+        })),
+        None => Ok(None),
+    }
+}
@@ -0,0 +1,71 @@
+use anyhow::anyhow;
+use sqlx::{Pool, Postgres, Row};
+
+use crate::metrics::db_timer;
+
+pub async fn fetch_chat_managers(pool: &Pool<Postgres>, chat_id: i64) -> anyhow::Result<Vec<i64>> {
+    let _timer = db_timer("fetch_chat_managers");
+
+    let rows = sqlx::query("SELECT user_id FROM chat_managers WHERE chat_id = $1 ORDER BY user_id")
+        .bind(chat_id)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("Error while getting managers of chat {}: {}", chat_id, e))?;
+
+    Ok(rows.into_iter().map(|row| row.get(0)).collect())
+}
+
+/// False when the user already was a manager
+pub async fn add_chat_manager(
+    pool: &Pool<Postgres>,
+    chat_id: i64,
+    user_id: i64,
+    added_by: i64,
+) -> anyhow::Result<bool> {
+    let _timer = db_timer("add_chat_manager");
+
+    let result = sqlx::query(
+        "INSERT INTO chat_managers (chat_id, user_id, added_by) VALUES ($1, $2, $3)
+         ON CONFLICT (chat_id, user_id) DO NOTHING",
+    )
+    .bind(chat_id)
+    .bind(user_id)
+    .bind(added_by)
+    .execute(pool)
+    .await
+    .map_err(|e| {
+        anyhow!(
+            "Failed to add manager {} to chat {}: {}",
+            user_id,
+            chat_id,
+            e
+        )
+    })?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// False when the user wasn't a manager
+pub async fn remove_chat_manager(
+    pool: &Pool<Postgres>,
+    chat_id: i64,
+    user_id: i64,
+) -> anyhow::Result<bool> {
+    let _timer = db_timer("remove_chat_manager");
+
+    let result = sqlx::query("DELETE FROM chat_managers WHERE chat_id = $1 AND user_id = $2")
+        .bind(chat_id)
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            anyhow!(
+                "Failed to remove manager {} from chat {}: {}",
+                user_id,
+                chat_id,
+                e
+            )
+        })?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -1,3 +1,7 @@
 pub mod chat;
 pub mod demands;
+pub mod errors;
+pub mod managers;
 pub mod tokens;
+pub mod universe;
+pub mod wallets;
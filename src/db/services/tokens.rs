@@ -1,17 +1,18 @@
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use sqlx::{FromRow, Row};
+use sqlx::{FromRow, Pool, Postgres, Row};
 
-use std::ops::Deref;
-
-use crate::global_data::{get_pool, TokenMapping};
+use crate::metrics::db_timer;
+use crate::state::{PerpMapping, TokenMapping};
 
 #[derive(Debug, Serialize, Clone, Deserialize)]
 pub struct TokensAt {
     pub timestamp_in_min: i32, // Matches INTEGER
     pub times: Vec<String>,    // Matches TEXT[]
     pub tokens: TokenMapping,  // Matches JSONB
+    #[serde(default)]
+    pub perps: PerpMapping, // Matches JSONB, NULL for rows stored before perps
 }
 
 // Custom FromRow implementation to handle JSONB field deserialization for TokensAt
@@ -27,22 +28,34 @@ impl<'r> FromRow<'r, sqlx::postgres::PgRow> for TokensAt {
                 source: Box::new(e),
             })?;
 
+        let perps_value: Option<Value> = row.try_get("perps")?;
+        let perps: PerpMapping = match perps_value {
+            Some(value) => {
+                serde_json::from_value(value).map_err(|e| sqlx::Error::ColumnDecode {
+                    index: "perps".into(),
+                    source: Box::new(e),
+                })?
+            }
+            None => PerpMapping::new(),
+        };
+
         Ok(Self {
             timestamp_in_min,
             times,
             tokens,
+            perps,
         })
     }
 }
 
 impl TokensAt {
-    pub async fn insert(&self) -> anyhow::Result<u64> {
-        let pool = get_pool();
+    pub async fn insert(&self, pool: &Pool<Postgres>) -> anyhow::Result<u64> {
+        let _timer = db_timer("insert_tokens_at");
 
         sqlx::query(
             r#"
-            INSERT INTO tokens_at (timestamp_in_min, times, tokens)
-            VALUES ($1, $2, $3)
+            INSERT INTO tokens_at (timestamp_in_min, times, tokens, perps)
+            VALUES ($1, $2, $3, $4)
             "#,
         )
         .bind(self.timestamp_in_min)
@@ -51,19 +64,26 @@ impl TokensAt {
             serde_json::to_value(&self.tokens)
                 .map_err(|e| anyhow!("Failed to serialize tokens: {}", e))?,
         )
-        .execute(pool.deref())
+        .bind(
+            serde_json::to_value(&self.perps)
+                .map_err(|e| anyhow!("Failed to serialize perps: {}", e))?,
+        )
+        .execute(pool)
         .await
         .map(|result| result.rows_affected())
         .map_err(|e| anyhow!("Failed to insert tokens: {}", e))
     }
 }
 
-pub async fn fetch_latest_tokens_at(time: &str) -> anyhow::Result<Option<TokensAt>> {
-    let pool = get_pool();
+pub async fn fetch_latest_tokens_at(
+    pool: &Pool<Postgres>,
+    time: &str,
+) -> anyhow::Result<Option<TokensAt>> {
+    let _timer = db_timer("fetch_latest_tokens_at");
 
     let row = sqlx::query(
         r#"
-        SELECT timestamp_in_min, times, tokens
+        SELECT timestamp_in_min, times, tokens, perps
         FROM tokens_at
         WHERE $1 = ANY(times)
         ORDER BY timestamp_in_min DESC
@@ -71,7 +91,7 @@ pub async fn fetch_latest_tokens_at(time: &str) -> anyhow::Result<Option<TokensA
         "#,
     )
     .bind(time)
-    .fetch_optional(pool.deref())
+    .fetch_optional(pool)
     .await
     .map_err(|e| anyhow!("Query failed: {}", e))?;
 
@@ -83,3 +103,31 @@ pub async fn fetch_latest_tokens_at(time: &str) -> anyhow::Result<Option<TokensA
         Ok(None)
     }
 }
+
+/// Snapshots in time order, bounds in minutes and inclusive, for the replay
+pub async fn fetch_tokens_at_between(
+    pool: &Pool<Postgres>,
+    since_min: Option<i32>,
+    until_min: Option<i32>,
+) -> anyhow::Result<Vec<TokensAt>> {
+    let _timer = db_timer("fetch_tokens_at_between");
+
+    let rows = sqlx::query(
+        r#"
+        SELECT timestamp_in_min, times, tokens, perps
+        FROM tokens_at
+        WHERE ($1::INTEGER IS NULL OR timestamp_in_min >= $1)
+        AND ($2::INTEGER IS NULL OR timestamp_in_min <= $2)
+        ORDER BY timestamp_in_min
+        "#,
+    )
+    .bind(since_min)
+    .bind(until_min)
+    .fetch_all(pool)
+    .await
+    .map_err(|e| anyhow!("Query failed: {}", e))?;
+
+    rows.iter()
+        .map(|row| TokensAt::from_row(row).map_err(|e| anyhow!("Bad snapshot: {}", e)))
+        .collect()
+}
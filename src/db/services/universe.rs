@@ -0,0 +1,107 @@
+use anyhow::anyhow;
+use sqlx::{Pool, Postgres, Row};
+
+use crate::metrics::db_timer;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownToken {
+    pub token_index: i32,
+    pub name: String,
+    pub token_id: String,
+    pub is_canonical: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KnownPair {
+    pub pair_index: i32,
+    pub name: String,
+    pub base_index: i32,
+    pub quote_index: i32,
+}
+
+pub async fn fetch_known_tokens(pool: &Pool<Postgres>) -> anyhow::Result<Vec<KnownToken>> {
+    let _timer = db_timer("fetch_known_tokens");
+
+    let rows = sqlx::query("SELECT token_index, name, token_id, is_canonical FROM known_tokens")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch known tokens: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| KnownToken {
+            token_index: row.get("token_index"),
+            name: row.get("name"),
+            token_id: row.get("token_id"),
+            is_canonical: row.get("is_canonical"),
+        })
+        .collect())
+}
+
+pub async fn fetch_known_pairs(pool: &Pool<Postgres>) -> anyhow::Result<Vec<KnownPair>> {
+    let _timer = db_timer("fetch_known_pairs");
+
+    let rows = sqlx::query("SELECT pair_index, name, base_index, quote_index FROM known_pairs")
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch known pairs: {}", e))?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| KnownPair {
+            pair_index: row.get("pair_index"),
+            name: row.get("name"),
+            base_index: row.get("base_index"),
+            quote_index: row.get("quote_index"),
+        })
+        .collect())
+}
+
+/// Replace the stored universe with the one just fetched, in a single transaction
+pub async fn save_known_universe(
+    pool: &Pool<Postgres>,
+    tokens: &[KnownToken],
+    pairs: &[KnownPair],
+) -> anyhow::Result<()> {
+    let _timer = db_timer("save_known_universe");
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("DELETE FROM known_tokens")
+        .execute(&mut tx)
+        .await
+        .map_err(|e| anyhow!("Failed to clear known tokens: {}", e))?;
+    sqlx::query("DELETE FROM known_pairs")
+        .execute(&mut tx)
+        .await
+        .map_err(|e| anyhow!("Failed to clear known pairs: {}", e))?;
+
+    for token in tokens {
+        sqlx::query(
+            "INSERT INTO known_tokens (token_index, name, token_id, is_canonical)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(token.token_index)
+        .bind(&token.name)
+        .bind(&token.token_id)
+        .bind(token.is_canonical)
+        .execute(&mut tx)
+        .await
+        .map_err(|e| anyhow!("Failed to insert known token {}: {}", token.name, e))?;
+    }
+    for pair in pairs {
+        sqlx::query(
+            "INSERT INTO known_pairs (pair_index, name, base_index, quote_index)
+             VALUES ($1, $2, $3, $4)",
+        )
+        .bind(pair.pair_index)
+        .bind(&pair.name)
+        .bind(pair.base_index)
+        .bind(pair.quote_index)
+        .execute(&mut tx)
+        .await
+        .map_err(|e| anyhow!("Failed to insert known pair {}: {}", pair.name, e))?;
+    }
+
+    tx.commit().await?;
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use anyhow::anyhow;
+use serde_json::Value;
+use sqlx::{Pool, Postgres, Row};
+use std::collections::HashMap;
+
+use crate::{metrics::db_timer, types::commands::WATCH};
+
+/// Polling state of a watched wallet, shared by every chat watching it
+#[derive(Debug, Clone, Default)]
+pub struct WalletState {
+    pub address: String,
+    pub last_fill_time: i64,
+    pub last_ledger_time: i64,
+    pub holdings: HashMap<String, f64>, // "spot:COIN" / "perp:COIN" -> size
+}
+
+pub async fn fetch_wallet_state(
+    pool: &Pool<Postgres>,
+    address: &str,
+) -> anyhow::Result<Option<WalletState>> {
+    let _timer = db_timer("fetch_wallet_state");
+
+    let row = sqlx::query(
+        "SELECT address, last_fill_time, last_ledger_time, holdings
+         FROM watched_wallets
+         WHERE address = $1",
+    )
+    .bind(address)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Failed to fetch wallet state for {}: {}", address, e))?;
+
+    match row {
+        Some(row) => {
+            let holdings: Value = row.try_get("holdings")?;
+            Ok(Some(WalletState {
+                address: row.try_get("address")?,
+                last_fill_time: row.try_get("last_fill_time")?,
+                last_ledger_time: row.try_get("last_ledger_time")?,
+                holdings: serde_json::from_value(holdings)?,
+            }))
+        }
+        None => Ok(None),
+    }
+}
+
+pub async fn save_wallet_state(pool: &Pool<Postgres>, state: &WalletState) -> anyhow::Result<()> {
+    let _timer = db_timer("save_wallet_state");
+
+    sqlx::query(
+        "INSERT INTO watched_wallets (address, last_fill_time, last_ledger_time, holdings)
+         VALUES ($1, $2, $3, $4)
+         ON CONFLICT (address) DO UPDATE
+         SET last_fill_time = $2, last_ledger_time = $3, holdings = $4",
+    )
+    .bind(&state.address)
+    .bind(state.last_fill_time)
+    .bind(state.last_ledger_time)
+    .bind(serde_json::to_value(&state.holdings)?)
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Failed to save wallet state for {}: {}", state.address, e))?;
+
+    Ok(())
+}
+
+/// Drop the polling state of wallets no chat is watching anymore
+pub async fn delete_unwatched_wallets(pool: &Pool<Postgres>) -> anyhow::Result<u64> {
+    let _timer = db_timer("delete_unwatched_wallets");
+
+    sqlx::query(
+        "DELETE FROM watched_wallets
+         WHERE address NOT IN (SELECT token FROM demands WHERE type_of = $1)",
+    )
+    .bind(WATCH)
+    .execute(pool)
+    .await
+    .map(|result| result.rows_affected())
+    .map_err(|e| anyhow!("Failed to delete unwatched wallets: {}", e))
+}
@@ -1,28 +1,72 @@
 -- Create the chat table
 CREATE TABLE chat (
-    id BIGINT PRIMARY KEY
+    id BIGINT PRIMARY KEY,
+    public_commands BOOLEAN NOT NULL DEFAULT FALSE -- everyone can use /demands and /price
 );
 
 -- Create the demands table with a composite primary key and foreign key reference to chat
 CREATE TABLE demands (
     chat_id BIGINT NOT NULL,
+    thread_id INTEGER,
     type_of VARCHAR NOT NULL,
     token VARCHAR,
     percentage SMALLINT,
     interval VARCHAR,
+    threshold_usd BIGINT NOT NULL DEFAULT 0, -- market cap level of 'mcap' demands
+    label VARCHAR,
     CONSTRAINT fk_chat
         FOREIGN KEY (chat_id)
         REFERENCES chat(id)
         ON DELETE CASCADE,
-    PRIMARY KEY (chat_id, type_of, token, percentage, interval)  -- Composite primary key with all columns
+    PRIMARY KEY (chat_id, type_of, token, percentage, interval, threshold_usd)  -- Composite primary key with all columns
 );
 
 -- Create the tokens_at table with times as VARCHAR[] instead of TEXT[]
 CREATE TABLE tokens_at (
     timestamp_in_min INTEGER PRIMARY KEY,
     times VARCHAR[],  -- Changed to VARCHAR[] for faster lookup and indexing
-    tokens JSONB
+    tokens JSONB,
+    perps JSONB
 );
 
 -- Create an index on chat_id for demands for faster lookups
 CREATE INDEX idx_demands_chat_id ON demands(chat_id);
+
+-- Spot universe seen at the previous fetch, used to detect listings and delistings
+CREATE TABLE known_tokens (
+    token_index INTEGER PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    token_id VARCHAR NOT NULL,
+    is_canonical BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE known_pairs (
+    pair_index INTEGER PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    base_index INTEGER NOT NULL,
+    quote_index INTEGER NOT NULL
+);
+
+-- Polling state of watched wallets, the watching chats are demands of type 'watch'
+CREATE TABLE watched_wallets (
+    address VARCHAR PRIMARY KEY,
+    last_fill_time BIGINT NOT NULL,
+    last_ledger_time BIGINT NOT NULL,
+    holdings JSONB NOT NULL
+);
+
+-- References given with unexpected errors, so support can look them up
+CREATE TABLE error_references (
+    reference VARCHAR PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    message TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
+
+-- Non-admin users a chat's administrators allowed to manage its alerts
+CREATE TABLE chat_managers (
+    chat_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    added_by BIGINT NOT NULL,
+    PRIMARY KEY (chat_id, user_id)
+);
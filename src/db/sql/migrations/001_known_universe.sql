@@ -0,0 +1,15 @@
+-- Spot universe seen at the previous fetch, used to detect listings and delistings.
+-- Empty until the first fetch, which records the universe without alerting.
+CREATE TABLE IF NOT EXISTS known_tokens (
+    token_index INTEGER PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    token_id VARCHAR NOT NULL,
+    is_canonical BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE TABLE IF NOT EXISTS known_pairs (
+    pair_index INTEGER PRIMARY KEY,
+    name VARCHAR NOT NULL,
+    base_index INTEGER NOT NULL,
+    quote_index INTEGER NOT NULL
+);
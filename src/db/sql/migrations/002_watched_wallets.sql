@@ -0,0 +1,10 @@
+-- Label given to a watched wallet, shown in its alerts
+ALTER TABLE demands ADD COLUMN IF NOT EXISTS label VARCHAR;
+
+-- Polling state of watched wallets, the watching chats are demands of type 'watch'
+CREATE TABLE IF NOT EXISTS watched_wallets (
+    address VARCHAR PRIMARY KEY,
+    last_fill_time BIGINT NOT NULL,
+    last_ledger_time BIGINT NOT NULL,
+    holdings JSONB NOT NULL
+);
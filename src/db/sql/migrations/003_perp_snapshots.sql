@@ -0,0 +1,2 @@
+-- Perp funding and open interest stored with each snapshot
+ALTER TABLE tokens_at ADD COLUMN IF NOT EXISTS perps JSONB;
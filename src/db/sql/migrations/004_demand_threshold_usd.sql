@@ -0,0 +1,5 @@
+-- Market cap demands keep their level in USD, beyond what the SMALLINT percentage holds.
+-- The column is part of the key so a token can have several levels.
+ALTER TABLE demands ADD COLUMN IF NOT EXISTS threshold_usd BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE demands DROP CONSTRAINT IF EXISTS demands_pkey;
+ALTER TABLE demands ADD PRIMARY KEY (chat_id, type_of, token, percentage, interval, threshold_usd);
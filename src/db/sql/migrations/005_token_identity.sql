@@ -0,0 +1,29 @@
+-- Spot demands and snapshots used to reference tokens by upper cased name,
+-- they now use the Hyperliquid token index. Names come from known_tokens,
+-- filled by the listing check, duplicated names resolve to the canonical token,
+-- then to the lowest index. `migrate` seeds an empty known_tokens from the info
+-- endpoint before running it and lists the demands left unresolved after.
+
+-- Spot demands: name -> token index
+UPDATE demands d
+SET token = k.token_index::VARCHAR
+FROM (
+    SELECT DISTINCT ON (UPPER(name)) UPPER(name) AS name, token_index
+    FROM known_tokens
+    ORDER BY UPPER(name), is_canonical DESC, token_index
+) k
+WHERE d.type_of IN ('alert', 'spread', 'depth', 'mcap')
+AND d.token = k.name;
+
+-- Snapshots: re-key the tokens JSONB by token index
+UPDATE tokens_at t
+SET tokens = (
+    SELECT COALESCE(jsonb_object_agg(COALESCE(k.token_index::VARCHAR, e.key), e.value), '{}'::jsonb)
+    FROM jsonb_each(t.tokens) e
+    LEFT JOIN (
+        SELECT DISTINCT ON (UPPER(name)) UPPER(name) AS name, token_index
+        FROM known_tokens
+        ORDER BY UPPER(name), is_canonical DESC, token_index
+    ) k ON k.name = e.key
+)
+WHERE t.tokens IS NOT NULL;
@@ -0,0 +1,7 @@
+-- References given with unexpected errors, so support can look them up
+CREATE TABLE IF NOT EXISTS error_references (
+    reference VARCHAR PRIMARY KEY,
+    user_id BIGINT NOT NULL,
+    message TEXT NOT NULL,
+    created_at BIGINT NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Non-admin users a chat's administrators allowed to manage its alerts
+CREATE TABLE IF NOT EXISTS chat_managers (
+    chat_id BIGINT NOT NULL,
+    user_id BIGINT NOT NULL,
+    added_by BIGINT NOT NULL,
+    PRIMARY KEY (chat_id, user_id)
+);
@@ -0,0 +1,2 @@
+-- Chats where everyone can use the read-only commands (/demands, /price)
+ALTER TABLE chat ADD COLUMN IF NOT EXISTS public_commands BOOLEAN NOT NULL DEFAULT FALSE;
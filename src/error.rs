@@ -0,0 +1,129 @@
+// src/error.rs
+
+use std::fmt;
+
+use teloxide::types::{ChatId, ThreadId};
+
+use crate::{
+    bot::{send_error, send_error_to_moderator},
+    context::AppContext,
+    hyperliquid::error::FetchError,
+};
+
+pub type BotResult<T> = Result<T, BotError>;
+
+/// Errors of the command and callback handlers.
+/// The first three are the user's to fix and are shown as is (MarkdownV2),
+/// the others are ours and go to the moderator. Errors nobody classified are internal.
+#[derive(Debug)]
+pub enum BotError {
+    UserInput(String),
+    Permission(String),
+    Quota(String),
+    Upstream(anyhow::Error),
+    Database(anyhow::Error),
+    Internal(anyhow::Error),
+}
+
+const INTERNAL_ERROR_MSG: &str = "Something went wrong on our side, the team has been notified";
+
+impl BotError {
+    pub fn user_input(msg: impl Into<String>) -> Self {
+        BotError::UserInput(msg.into())
+    }
+
+    /// Parsers build their messages with `anyhow!` for the user to read
+    pub fn from_parser(err: anyhow::Error) -> Self {
+        BotError::UserInput(err.to_string())
+    }
+
+    pub fn is_internal(&self) -> bool {
+        matches!(
+            self,
+            BotError::Upstream(_) | BotError::Database(_) | BotError::Internal(_)
+        )
+    }
+
+    /// What the user gets to see
+    pub fn user_message(&self) -> &str {
+        match self {
+            BotError::UserInput(msg) | BotError::Permission(msg) | BotError::Quota(msg) => msg,
+            BotError::Upstream(_) | BotError::Database(_) | BotError::Internal(_) => {
+                INTERNAL_ERROR_MSG
+            }
+        }
+    }
+
+    /// Show the error in the chat, escalating the internal ones
+    pub fn report(&self, ctx: &AppContext, chat_id: ChatId, thread_id: Option<ThreadId>) {
+        if self.is_internal() {
+            send_error_to_moderator(ctx, format!("{} in chat {}: {:?}", self, chat_id, self));
+        }
+        send_error(ctx, chat_id, self.user_message(), thread_id);
+    }
+}
+
+impl fmt::Display for BotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BotError::UserInput(msg) => write!(f, "User input error: {}", msg),
+            BotError::Permission(msg) => write!(f, "Permission error: {}", msg),
+            BotError::Quota(msg) => write!(f, "Quota error: {}", msg),
+            BotError::Upstream(e) => write!(f, "Upstream error: {}", e),
+            BotError::Database(e) => write!(f, "Database error: {}", e),
+            BotError::Internal(e) => write!(f, "Internal error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for BotError {}
+
+impl From<sqlx::Error> for BotError {
+    fn from(err: sqlx::Error) -> Self {
+        BotError::Database(err.into())
+    }
+}
+
+impl From<FetchError> for BotError {
+    fn from(err: FetchError) -> Self {
+        BotError::Upstream(err.into())
+    }
+}
+
+impl From<reqwest::Error> for BotError {
+    fn from(err: reqwest::Error) -> Self {
+        BotError::Upstream(err.into())
+    }
+}
+
+impl From<teloxide::RequestError> for BotError {
+    fn from(err: teloxide::RequestError) -> Self {
+        BotError::Upstream(err.into())
+    }
+}
+
+impl From<std::num::ParseIntError> for BotError {
+    fn from(err: std::num::ParseIntError) -> Self {
+        BotError::UserInput(err.to_string())
+    }
+}
+
+/// User errors are built explicitly, see `from_parser`, anything not recognised is internal
+impl From<anyhow::Error> for BotError {
+    fn from(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<BotError>() {
+            Ok(bot_error) => return bot_error,
+            Err(err) => err,
+        };
+        if err.is::<sqlx::Error>() {
+            BotError::Database(err)
+        } else if err.is::<FetchError>()
+            || err.is::<reqwest::Error>()
+            || err.is::<teloxide::RequestError>()
+        {
+            BotError::Upstream(err)
+        } else {
+            BotError::Internal(err)
+        }
+    }
+}
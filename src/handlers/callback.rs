@@ -1,74 +1,116 @@
-use anyhow::anyhow;
-use teloxide::{types::CallbackQuery, Bot};
+use teloxide::types::{CallbackQuery, ChatId, Message, ThreadId};
 
 use crate::{
-    bot::{msg_delete::delete_message, send_error, send_message},
-    db::services::demands::{delete_demand_by_composite_id, Demand},
+    bot::{
+        answer_callback, msg_delete::delete_message, send_error_to_moderator, send_message,
+        utils::plain_text,
+    },
+    context::AppContext,
+    db::services::demands::get_demands_by_chat_id,
+    error::{BotError, BotResult},
 };
 
-use super::commands::check_if_from_admin;
+use crate::types::commands::SET_ALERT_CALLBACK_PREFIX;
 
-pub async fn callback_handler(_: Bot, q: CallbackQuery) -> anyhow::Result<()> {
-    if let Some(maybe_message) = q.message.clone() {
-        if let Some(message) = maybe_message.regular_message() {
-            let chat_id = message.chat.id;
-            let thread_id = message.thread_id;
-
-            let callback_data = q
-                .data
-                .as_deref()
-                .ok_or_else(|| anyhow!("Callback data is empty"))?;
+use super::{
+    commands::{check_if_from_admin, handle_set_alert_callback},
+    permissions::ADMIN_ONLY_ERR,
+};
 
-            let opts: Vec<&str> = callback_data.split('_').collect();
-            debug!("Callback data {}", callback_data);
-            let (chat_id_bis, composite_id) = match (opts.get(0), opts.get(1)) {
-                (Some(chat_id_str), Some(composite_id)) => match chat_id_str.parse::<i64>() {
-                    Ok(chat_id) => Ok((chat_id, composite_id.to_string())),
-                    Err(_) => {
-                        let err = format!("Invalid chat_id format: {}", chat_id_str);
-                        send_error(chat_id, &err, thread_id);
-                        Err(anyhow!(err))
-                    }
-                },
-                _ => {
-                    let err = format!("Invalid callback data format: {}", callback_data);
-                    send_error(chat_id, &err, thread_id);
-                    Err(anyhow!(err))
-                }
-            }?;
+const EXPIRED_CALLBACK_MSG: &str = "This message is too old, ask again";
 
-            let (d_chat_id, _, _, _, _) = match Demand::parse_composite_id(&composite_id) {
-                Some(parsed) => parsed,
-                None => {
-                    let err = format!("Failed to parse demand from composite_id: {}", composite_id);
-                    send_error(chat_id, &err, thread_id);
-                    return Err(anyhow!(err));
-                }
-            };
+/// Every press is answered: a toast on success, an alert to acknowledge on error
+pub async fn callback_handler(ctx: AppContext, q: CallbackQuery) -> anyhow::Result<()> {
+    let result = match q.message.as_ref().and_then(|m| m.regular_message()) {
+        Some(message) => {
+            handle_callback(&ctx, &q, message, message.chat.id, message.thread_id).await
+        }
+        None => Err(BotError::user_input(EXPIRED_CALLBACK_MSG)),
+    };
 
-            if chat_id.0 != d_chat_id || d_chat_id != chat_id_bis {
-                let err = format!("Chat ID mismatch: {} ! {}", chat_id, d_chat_id);
-                send_error(chat_id, &err, thread_id);
-                return Err(anyhow!(err));
-            }
-            match check_if_from_admin(message.clone(), Some(q.from)).await {
-                Ok(None) => {
-                    return Ok(());
-                }
-                Err(e) => error!("{}", e),
-                _ => info!("ok"),
+    match result {
+        Ok(toast) => {
+            answer_callback(&ctx, q.id.clone(), toast, false);
+            Ok(())
+        }
+        Err(err) => {
+            error!("Callback error: {:?}", err);
+            if err.is_internal() {
+                send_error_to_moderator(&ctx, format!("{} in callback: {:?}", err, err));
             }
-            if let Err(e) = delete_demand_by_composite_id(&composite_id).await {
-                let err = format!("Failed to delete demand: {}", e);
-                error!("{}", err);
-                send_error(chat_id, &err, thread_id);
-                return Err(anyhow!(err));
+            answer_callback(&ctx, q.id.clone(), plain_text(err.user_message()), true);
+            Err(err.into())
+        }
+    }
+}
+
+/// Returns the toast shown to the user
+async fn handle_callback(
+    ctx: &AppContext,
+    q: &CallbackQuery,
+    message: &Message,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+) -> BotResult<String> {
+    let callback_data = q
+        .data
+        .as_deref()
+        .ok_or_else(|| BotError::user_input("Callback data is empty"))?;
+
+    debug!("Callback data {}", callback_data);
+    if let Some(data) = callback_data.strip_prefix(SET_ALERT_CALLBACK_PREFIX) {
+        if check_if_from_admin(ctx, message.clone(), Some(q.from.clone()))
+            .await?
+            .is_none()
+        {
+            return Err(BotError::Permission(ADMIN_ONLY_ERR.to_string()));
+        }
+        let msg = handle_set_alert_callback(ctx, chat_id, thread_id, data).await?;
+        send_message(ctx, chat_id, &msg, thread_id);
+        delete_message(ctx, message, chat_id);
+        return Ok("Alert set".to_string());
+    }
+
+    let opts: Vec<&str> = callback_data.split('_').collect();
+    let (chat_id_bis, button_key) = match (opts.first(), opts.get(1)) {
+        (Some(chat_id_str), Some(button_key)) => match chat_id_str.parse::<i64>() {
+            Ok(chat_id) => (chat_id, button_key.to_string()),
+            Err(_) => {
+                return Err(BotError::user_input(format!(
+                    "Invalid chat_id format: {}",
+                    chat_id_str
+                )))
             }
-            send_message(chat_id, "Demand erased", thread_id);
-            delete_message(message, chat_id);
-        } else {
-            send_message(maybe_message.chat().id, "Mesage too old", None);
+        },
+        _ => {
+            return Err(BotError::user_input(format!(
+                "Invalid callback data format: {}",
+                callback_data
+            )))
         }
+    };
+
+    if chat_id.0 != chat_id_bis {
+        return Err(BotError::Permission(format!(
+            "Chat ID mismatch: {} ! {}",
+            chat_id, chat_id_bis
+        )));
+    }
+    if check_if_from_admin(ctx, message.clone(), Some(q.from.clone()))
+        .await?
+        .is_none()
+    {
+        return Err(BotError::Permission(ADMIN_ONLY_ERR.to_string()));
     }
-    Ok(())
+    let demand = get_demands_by_chat_id(&ctx.pool, chat_id.0)
+        .await
+        .map_err(BotError::Database)?
+        .into_iter()
+        .find(|demand| demand.get_button_key() == button_key)
+        // Pressed twice, or deleted since the list was sent
+        .ok_or_else(|| BotError::user_input("This alert isn't set anymore"))?;
+    demand.delete_demand(ctx).await?;
+    send_message(ctx, chat_id, "Demand erased", thread_id);
+    delete_message(ctx, message, chat_id);
+    Ok("Demand erased".to_string())
 }
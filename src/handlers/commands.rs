@@ -1,50 +1,154 @@
 use crate::{
-    bot::{send_error, send_error_to_moderator, send_message},
+    bot::{
+        send_ephemeral, send_message, send_message_with_button,
+        utils::{escape_user_text, format_usd_compact},
+    },
     constants::schedules::parse_interval,
+    context::AppContext,
     db::services::demands::{
-        delete_demands_for_chat, get_demands_by_chat_id, send_demands_for, Demand,
+        delete_demands_for_chat, format_demand_for_message, get_demands_by_chat_id,
+        send_demands_for, Demand,
+    },
+    error::{BotError, BotResult},
+    handlers::{
+        permissions::{
+            allows_public_commands, can_manage_alerts, handle_manager_command,
+            handle_public_command, ADMIN_ONLY_ERR,
+        },
+        transfer::{handle_export_command, handle_import_command, handle_sync_command},
+    },
+    hyperliquid::{
+        fetch_price::Token,
+        token_lookup::{lookup_token, TokenLookup},
+    },
+    procedures::pump_alert::diff_in_percent,
+    types::commands::{
+        parse_alert, parse_funding, parse_mcap, parse_threshold, parse_watch, switch_type, Command,
+        ALERT, DEPTH, DEPTH_PARSE_ERR, LISTING, MCAP, OI_CHANGE, SET_ALERT_CALLBACK_PREFIX,
+        SPECIAL, SPREAD, SPREAD_PARSE_ERR, WATCH,
     },
-    global_data::{get_amount_from_map_for_chat_id, get_bot, get_token_array},
-    types::commands::{parse_alert, switch_type, Command, ALERT, SPECIAL},
 };
 use anyhow::anyhow;
 use log::{debug, error, info};
+use std::time::Duration;
 use teloxide::{
     prelude::*,
-    types::{ThreadId, User},
+    types::{InlineKeyboardButton, InlineKeyboardMarkup, ThreadId, User},
 };
 
-const ADMIN_CHAT_ID: i64 = 2171722969;
-
-pub async fn commands_handler(_: Bot, message: Message, command: Command) -> anyhow::Result<()> {
-    // Early returns for auth checks
-    if check_if_from_admin(message.clone(), None).await?.is_none() {
-        return Ok(());
-    }
-    debug!("Asked");
-    let _ = verify_user(&message)?;
+pub async fn commands_handler(
+    ctx: AppContext,
+    message: Message,
+    command: Command,
+) -> anyhow::Result<()> {
     let chat_id = message.chat.id;
     let thread_id = message.thread_id;
 
-    let result = match command {
-        Command::Free => handle_free_command(chat_id).await,
-        Command::Demands => handle_demands_command(chat_id, thread_id).await,
-        Command::SetAlert { str } => handle_set_alert(chat_id, thread_id, str).await,
-        Command::Special { switch } => handle_special_command(chat_id, thread_id, switch).await,
-        Command::Help => Ok(HELP_MESSAGE.to_string()),
+    // Early returns for auth checks
+    let result = match check_if_from_admin(&ctx, message.clone(), None).await {
+        Ok(Some(())) => {
+            debug!("Asked");
+            verify_user(&message)?;
+            run_command(&ctx, &message, command).await
+        }
+        Ok(None) => match is_public_command(&ctx, chat_id, &command).await {
+            Ok(true) => run_public_command(&ctx, chat_id, thread_id, command).await,
+            Ok(false) => {
+                reply_to_non_admin(&ctx, chat_id, thread_id);
+                return Ok(());
+            }
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
     };
 
     match result {
         Ok(message) if !message.is_empty() => {
-            send_message(chat_id, &message, thread_id);
+            send_message(&ctx, chat_id, &message, thread_id);
             Ok(())
         }
         Ok(_) => Ok(()),
         Err(err) => {
             error!("Command error: {:?}", err);
-            send_error(chat_id, &err.to_string(), thread_id);
-            Err(err)
+            err.report(&ctx, chat_id, thread_id);
+            Err(err.into())
+        }
+    }
+}
+
+async fn is_public_command(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    command: &Command,
+) -> BotResult<bool> {
+    if !command.is_read_only() {
+        return Ok(false);
+    }
+    allows_public_commands(ctx, chat_id).await
+}
+
+/// Read-only commands of the others, the demands are listed without delete buttons
+async fn run_public_command(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    command: Command,
+) -> BotResult<String> {
+    match command {
+        Command::Demands => handle_demands_command(ctx, chat_id, thread_id, false).await,
+        Command::Price { str } => handle_price_command(ctx, str).await,
+        Command::Help => Ok(HELP_MESSAGE.to_string()),
+        _ => Err(BotError::Permission(ADMIN_ONLY_ERR.to_string())),
+    }
+}
+
+/// The others are ignored unless `permissions.non_admin_reply_secs` is set,
+/// the reply is then deleted after that long
+fn reply_to_non_admin(ctx: &AppContext, chat_id: ChatId, thread_id: Option<ThreadId>) {
+    let lifetime = ctx.config.permissions.non_admin_reply_secs;
+    if lifetime == 0 {
+        debug!("Command from a non admin ignored in chat {}", chat_id);
+        return;
+    }
+    send_ephemeral(
+        ctx,
+        chat_id,
+        &format!("⚠️ {ADMIN_ONLY_ERR} ⚠️"),
+        thread_id,
+        Duration::from_secs(lifetime),
+    );
+}
+
+async fn run_command(ctx: &AppContext, message: &Message, command: Command) -> BotResult<String> {
+    let chat_id = message.chat.id;
+    let thread_id = message.thread_id;
+    match command {
+        Command::Free => handle_free_command(ctx, chat_id).await,
+        Command::Demands => handle_demands_command(ctx, chat_id, thread_id, true).await,
+        Command::SetAlert { str } => handle_set_alert(ctx, chat_id, thread_id, str).await,
+        Command::Special { switch } => {
+            handle_special_command(ctx, chat_id, thread_id, switch).await
+        }
+        Command::Listing { switch } => {
+            handle_listing_command(ctx, chat_id, thread_id, switch).await
         }
+        Command::Watch { str } => handle_watch_command(ctx, chat_id, thread_id, str).await,
+        Command::Funding { str } => handle_funding_command(ctx, chat_id, thread_id, str).await,
+        Command::Oi { str } => handle_oi_command(ctx, chat_id, thread_id, str).await,
+        Command::Spread { str } => {
+            handle_liquidity_command(ctx, chat_id, thread_id, str, SPREAD, SPREAD_PARSE_ERR).await
+        }
+        Command::Depth { str } => {
+            handle_liquidity_command(ctx, chat_id, thread_id, str, DEPTH, DEPTH_PARSE_ERR).await
+        }
+        Command::Mcap { str } => handle_mcap_command(ctx, chat_id, thread_id, str).await,
+        Command::Export { format } => handle_export_command(ctx, chat_id, thread_id, format).await,
+        Command::Import => handle_import_command(ctx, message).await,
+        Command::Sync { str } => handle_sync_command(ctx, message, str).await,
+        Command::Manager { str } => handle_manager_command(ctx, message, str).await,
+        Command::Public { switch } => handle_public_command(ctx, chat_id, switch).await,
+        Command::Price { str } => handle_price_command(ctx, str).await,
+        Command::Help => Ok(HELP_MESSAGE.to_string()),
     }
 }
 
@@ -66,8 +170,8 @@ fn verify_user(message: &Message) -> anyhow::Result<()> {
     }
 }
 
-async fn handle_free_command(chat_id: ChatId) -> anyhow::Result<String> {
-    delete_demands_for_chat(chat_id.0)
+async fn handle_free_command(ctx: &AppContext, chat_id: ChatId) -> BotResult<String> {
+    delete_demands_for_chat(ctx, chat_id.0)
         .await
         .map(|_| {
             info!("Deleted alerts for chat {}", chat_id.0);
@@ -75,58 +179,216 @@ async fn handle_free_command(chat_id: ChatId) -> anyhow::Result<String> {
         })
         .map_err(|e| {
             error!("Failed to delete alerts: {:?}", e);
-            anyhow!("Failed to delete alerts. Please try again later")
+            BotError::Database(e)
         })
 }
 
 async fn handle_demands_command(
+    ctx: &AppContext,
     chat_id: ChatId,
     thread_id: Option<ThreadId>,
-) -> anyhow::Result<String> {
-    match get_demands_by_chat_id(chat_id.0).await {
+    deletable: bool,
+) -> BotResult<String> {
+    match get_demands_by_chat_id(&ctx.pool, chat_id.0).await {
         Ok(demands) => {
-            send_demands_for(chat_id, thread_id, demands);
+            send_demands_for(
+                ctx,
+                chat_id,
+                thread_id,
+                demands,
+                &ctx.market.tokens().await,
+                deletable,
+            );
             Ok("".to_string())
         }
-        Err(e) => {
-            send_error_to_moderator(e.to_string());
-            Err(anyhow!("Failed to fetch demands. Please try again later"))
-        }
+        Err(e) => Err(BotError::Database(e)),
     }
 }
 
+/// Spot token by the /setalert lookup, a perp when no spot token matches
+async fn handle_price_command(ctx: &AppContext, query: String) -> BotResult<String> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(BotError::user_input("- `/price \\[TOKEN\\]`"));
+    }
+    let token = match resolve_token(ctx, query).await {
+        Ok(token) => token,
+        Err(err) => {
+            return match ctx.market.perps().await.get(&query.to_ascii_uppercase()) {
+                Some(perp) => Ok(format!(
+                    "*{}* perp: {}$\nFunding: {:.2}% annualized\nOpen interest: {}",
+                    perp.name,
+                    perp.mark_price,
+                    perp.annualized_funding(),
+                    format_usd_compact(perp.open_interest * perp.mark_price)
+                )),
+                None => Err(err),
+            }
+        }
+    };
+
+    let token_map = ctx.market.tokens().await;
+    let info = token_map.get(&token.index.to_string()).ok_or_else(|| {
+        BotError::user_input(format!(
+            "No price for {} yet",
+            escape_user_text(&token.name)
+        ))
+    })?;
+    Ok(format!(
+        "*{}*: {} \\({}% in 24h\\)\n{}",
+        escape_user_text(&info.name),
+        info.format_price(),
+        diff_in_percent(info.price, info.price_prev_24h),
+        info.format_caps()
+    ))
+}
+
 async fn handle_set_alert(
+    ctx: &AppContext,
     chat_id: ChatId,
     thread_id: Option<ThreadId>,
     alert: String,
-) -> anyhow::Result<String> {
-    check_demand(&chat_id).await?;
-    let (token, interval, percentage) = parse_alert(alert)?;
+) -> BotResult<String> {
+    check_demand(ctx, &chat_id).await?;
+    let (query, interval, percentage) = parse_alert(alert).map_err(BotError::from_parser)?;
 
-    let token = token.to_ascii_uppercase();
-    let token_array = get_token_array().await;
+    let standardized_interval = parse_interval(&interval)
+        .ok_or_else(|| BotError::user_input(format!("Invalid interval: {}", interval)))?;
+
+    match lookup_token(&ctx.market.universe().await, &query) {
+        TokenLookup::Found(token) => {
+            insert_alert(
+                ctx,
+                chat_id,
+                thread_id,
+                &token,
+                standardized_interval,
+                percentage,
+            )
+            .await
+        }
+        TokenLookup::Ambiguous(tokens) => {
+            let keyboard = tokens
+                .iter()
+                .map(|token| {
+                    vec![InlineKeyboardButton::callback(
+                        format!(
+                            "{} {} #{}",
+                            token.name,
+                            token.full_name.as_deref().unwrap_or_default(),
+                            token.index
+                        ),
+                        format!(
+                            "{SET_ALERT_CALLBACK_PREFIX}{}_{}_{}",
+                            token.index, standardized_interval, percentage
+                        ),
+                    )]
+                })
+                .collect::<Vec<_>>();
+            send_message_with_button(
+                ctx,
+                chat_id,
+                &format!(
+                    "Several tokens match '{}', pick one:",
+                    escape_user_text(&query)
+                ),
+                thread_id,
+                InlineKeyboardMarkup::new(keyboard),
+            );
+            Ok("".to_string())
+        }
+        TokenLookup::NotFound { suggestions } => Err(token_not_found(&query, &suggestions)),
+    }
+}
+
+/// Set-alert buttons carry `seta_{token_index}_{interval}_{percentage}`
+pub async fn handle_set_alert_callback(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    data: &str,
+) -> BotResult<String> {
+    let opts: Vec<&str> = data.split('_').collect();
+    let (token_index, interval, percentage) = match opts.as_slice() {
+        [token_index, interval, percentage] => (
+            token_index.parse::<usize>()?,
+            parse_interval(interval)
+                .ok_or_else(|| BotError::user_input(format!("Invalid interval: {}", interval)))?,
+            percentage.parse::<i16>()?,
+        ),
+        _ => {
+            return Err(BotError::user_input(format!(
+                "Invalid set alert callback: {}",
+                data
+            )))
+        }
+    };
+    check_demand(ctx, &chat_id).await?;
+
+    let universe = ctx.market.universe().await;
+    let token = universe
+        .tokens
+        .iter()
+        .find(|token| token.index == token_index)
+        .ok_or_else(|| {
+            BotError::user_input(format!("Token #{} doesn't exist anymore", token_index))
+        })?;
+    insert_alert(ctx, chat_id, thread_id, token, interval, percentage).await
+}
 
-    if !token_array.contains(&token) {
-        return Err(anyhow!("Token '{}' doesn't exist", token));
+/// Same lookup as /setalert for commands without disambiguation buttons
+async fn resolve_token(ctx: &AppContext, query: &str) -> BotResult<Token> {
+    match lookup_token(&ctx.market.universe().await, query) {
+        TokenLookup::Found(token) => Ok(token),
+        TokenLookup::Ambiguous(tokens) => Err(BotError::user_input(format!(
+            "Several tokens match '{}', use the token ID: {}",
+            escape_user_text(query),
+            tokens
+                .iter()
+                .map(|t| format!("{} `{}`", escape_user_text(&t.name), t.token_id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+        TokenLookup::NotFound { suggestions } => Err(token_not_found(query, &suggestions)),
     }
+}
 
-    let standardized_interval =
-        parse_interval(&interval).ok_or_else(|| anyhow!("Invalid interval: {}", interval))?;
+fn token_not_found(query: &str, suggestions: &[String]) -> BotError {
+    if suggestions.is_empty() {
+        BotError::user_input(format!("Token '{}' doesn't exist", escape_user_text(query)))
+    } else {
+        BotError::user_input(format!(
+            "Token '{}' doesn't exist, did you mean {} ?",
+            escape_user_text(query),
+            escape_user_text(&suggestions.join(", "))
+        ))
+    }
+}
 
+async fn insert_alert(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    token: &Token,
+    interval: &str,
+    percentage: i16,
+) -> BotResult<String> {
     let demand = Demand {
         chat_id: chat_id.0,
         thread_id: thread_id.map(|id| id.0 .0),
         type_of: ALERT.to_owned(),
-        token: token.clone(),
+        token: token.index.to_string(),
         percentage,
-        interval: standardized_interval.to_string(),
+        interval: interval.to_string(),
+        ..Default::default()
     };
 
-    demand.insert_to_db().await?;
+    demand.insert_to_db(ctx).await?;
 
     let mut message = format!(
         "Alert set for token {} at interval {}",
-        token, standardized_interval
+        escape_user_text(&token.name),
+        interval
     );
     if percentage != 0 {
         message += &format!(" for percentage {}", percentage);
@@ -135,68 +397,236 @@ async fn handle_set_alert(
 }
 
 async fn handle_special_command(
+    ctx: &AppContext,
     chat_id: ChatId,
     thread_id: Option<ThreadId>,
     switch: String,
-) -> anyhow::Result<String> {
-    match switch_type(switch) {
+) -> BotResult<String> {
+    match switch_type(switch, "special") {
         Ok(push) => {
             let demand = Demand::new(chat_id.0, SPECIAL.to_owned(), thread_id.map(|id| id.0 .0));
             if push {
-                check_demand(&chat_id).await?;
-                demand.insert_to_db().await?;
+                check_demand(ctx, &chat_id).await?;
+                demand.insert_to_db(ctx).await?;
                 Ok("Pump alert set for this channel".to_string())
             } else {
-                demand.delete_demand().await?;
+                demand.delete_demand(ctx).await?;
                 Ok("Pump alert  suppressed for this channel".to_string())
             }
         }
-        Err(e) => Err(e),
+        Err(e) => Err(BotError::from_parser(e)),
+    }
+}
+
+async fn handle_listing_command(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    switch: String,
+) -> BotResult<String> {
+    let push = switch_type(switch, "listing").map_err(BotError::from_parser)?;
+    let demand = Demand::new(chat_id.0, LISTING.to_owned(), thread_id.map(|id| id.0 .0));
+    if push {
+        check_demand(ctx, &chat_id).await?;
+        demand.insert_to_db(ctx).await?;
+        Ok("New listing alert set for this channel".to_string())
+    } else {
+        demand.delete_demand(ctx).await?;
+        Ok("New listing alert suppressed for this channel".to_string())
+    }
+}
+
+async fn handle_watch_command(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    watch: String,
+) -> BotResult<String> {
+    check_demand(ctx, &chat_id).await?;
+    let (address, label) = parse_watch(watch).map_err(BotError::from_parser)?;
+
+    let mut demand = Demand::new(chat_id.0, WATCH.to_owned(), thread_id.map(|id| id.0 .0));
+    demand.token = address.clone();
+    demand.label = label;
+    demand.insert_to_db(ctx).await?;
+
+    Ok(format!(
+        "Watching wallet `{}`, trades, large transfers and position changes will be posted here",
+        address
+    ))
+}
+
+async fn handle_funding_command(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    funding: String,
+) -> BotResult<String> {
+    check_demand(ctx, &chat_id).await?;
+    let (token, type_of, percentage) = parse_funding(funding).map_err(BotError::from_parser)?;
+
+    let token = token.to_ascii_uppercase();
+    if !ctx.market.perps().await.contains_key(&token) {
+        return Err(BotError::user_input(format!(
+            "Perp '{}' doesn't exist",
+            token
+        )));
+    }
+
+    let mut demand = Demand::new(chat_id.0, type_of, thread_id.map(|id| id.0 .0));
+    demand.token = token;
+    demand.percentage = percentage;
+    let message = format!(
+        "Funding alert set: {}",
+        format_demand_for_message(ctx, &demand, &ctx.market.tokens().await)
+    );
+    demand.insert_to_db(ctx).await?;
+
+    Ok(message)
+}
+
+async fn handle_oi_command(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    oi: String,
+) -> BotResult<String> {
+    check_demand(ctx, &chat_id).await?;
+    let (token, interval, percentage) = parse_alert(oi).map_err(BotError::from_parser)?;
+
+    let token = token.to_ascii_uppercase();
+    if !ctx.market.perps().await.contains_key(&token) {
+        return Err(BotError::user_input(format!(
+            "Perp '{}' doesn't exist",
+            token
+        )));
     }
+    if percentage <= 0 {
+        return Err(BotError::user_input("Percentage must be above 0"));
+    }
+    let standardized_interval = parse_interval(&interval)
+        .ok_or_else(|| BotError::user_input(format!("Invalid interval: {}", interval)))?;
+
+    let mut demand = Demand::new(chat_id.0, OI_CHANGE, thread_id.map(|id| id.0 .0));
+    demand.token = token.clone();
+    demand.percentage = percentage;
+    demand.interval = standardized_interval.to_string();
+    demand.insert_to_db(ctx).await?;
+
+    Ok(format!(
+        "Open interest alert set for {} at interval {} for percentage {}",
+        token, standardized_interval, percentage
+    ))
+}
+
+async fn handle_liquidity_command(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    input: String,
+    type_of: &str,
+    parse_err: &'static str,
+) -> BotResult<String> {
+    check_demand(ctx, &chat_id).await?;
+    let (query, threshold) = parse_threshold(input, parse_err).map_err(BotError::from_parser)?;
+    let token = resolve_token(ctx, &query).await?;
+
+    let mut demand = Demand::new(chat_id.0, type_of, thread_id.map(|id| id.0 .0));
+    demand.token = token.index.to_string();
+    demand.percentage = threshold;
+    let message = format!(
+        "Liquidity alert set: {}",
+        format_demand_for_message(ctx, &demand, &ctx.market.tokens().await)
+    );
+    demand.insert_to_db(ctx).await?;
+
+    Ok(message)
+}
+
+async fn handle_mcap_command(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    input: String,
+) -> BotResult<String> {
+    check_demand(ctx, &chat_id).await?;
+    let (query, threshold_usd) = parse_mcap(input).map_err(BotError::from_parser)?;
+    let token = resolve_token(ctx, &query).await?;
+
+    let mut demand = Demand::new(chat_id.0, MCAP, thread_id.map(|id| id.0 .0));
+    demand.token = token.index.to_string();
+    demand.threshold_usd = threshold_usd;
+    let message = format!(
+        "Market cap alert set: {}",
+        format_demand_for_message(ctx, &demand, &ctx.market.tokens().await)
+    );
+    demand.insert_to_db(ctx).await?;
+
+    Ok(message)
 }
 
 pub async fn check_if_from_admin(
+    ctx: &AppContext,
     message: Message,
     compare_id: Option<User>,
-) -> anyhow::Result<Option<()>> {
+) -> BotResult<Option<()>> {
     let user = match compare_id {
         Some(user) => Some(user),
         None => message.from,
     };
 
     if let Some(user) = user {
-        let bot = get_bot();
-        let admins = bot.get_chat_administrators(message.chat.id).await?;
-        debug!("Admins: {:?}", admins);
-
-        if admins.iter().any(|admin| admin.user.id == user.id) {
+        if can_manage_alerts(ctx, message.chat.id, user.id).await? {
             return Ok(Some(()));
         }
     }
     Ok(None)
 }
 
-pub async fn check_demand(chat_id: &ChatId) -> anyhow::Result<()> {
-    let current_count = get_amount_from_map_for_chat_id(chat_id.0).await;
+pub async fn check_demand(ctx: &AppContext, chat_id: &ChatId) -> BotResult<()> {
+    let current_count = ctx.chat_demands.get(chat_id.0).await;
     debug!(
         "Checking demand for chat {}: count = {}",
         chat_id.0, current_count
     );
 
-    if chat_id.0 != ADMIN_CHAT_ID && current_count >= 3 {
-        Err(anyhow!("Max demand reached. Free the demands or erase one"))
+    if !is_operator_chat(ctx, chat_id) && current_count >= ctx.config.quotas.max_demands_per_chat {
+        Err(BotError::Quota(
+            "Max demand reached. Free the demands or erase one".to_string(),
+        ))
     } else {
         Ok(())
     }
 }
 
+/// Operators have no quota in their own DM
+pub fn is_operator_chat(ctx: &AppContext, chat_id: &ChatId) -> bool {
+    chat_id
+        .as_user()
+        .is_some_and(|user_id| ctx.is_operator(user_id))
+}
+
 pub const HELP_MESSAGE: &str = "🤖 __*Wagmi Alert Bot*__\n\
 \n\
 __*Commands:*__\n\
 - `/free` → Delete all alerts\n\
 - `/special` → \\(on/start\\)/\\(off/stop\\)  erase or activate pump alert\n\
+- `/listing` → \\(on/start\\)/\\(off/stop\\)  erase or activate new listing alert\n\
 - `/demands` → Show all our alerts/special. Click to erase one\n\
-- `/setalert \\[TOKEN\\] \\[INTERVAL\\] Optional<PERCENTAGE>` → Set alert for token  \n\
+- `/setalert \\[TOKEN\\] \\[INTERVAL\\] Optional<PERCENTAGE>` → Set alert for token, by symbol, full name, token ID or @pair  \n\
+- `/watch \\[0xADDRESS\\] Optional<LABEL>` → Post trades, large transfers and position changes of a wallet\n\
+- `/funding \\[PERP\\] \\[above/below/flip\\] Optional<PERCENTAGE>` → Alert when annualized funding crosses a level or flips sign\n\
+- `/oi \\[PERP\\] \\[INTERVAL\\] \\[PERCENTAGE\\]` → Alert on open interest changes over the interval\n\
+- `/spread \\[TOKEN\\] \\[BPS\\]` → Alert when the spread widens beyond the bps\n\
+- `/depth \\[TOKEN\\] \\[K$\\]` → Alert when bid depth within 2% of mid drops below the thousands of $\n\
+- `/mcap \\[TOKEN\\] \\[MARKET_CAP\\]` → Alert when the market cap crosses the level \\(500k, 10m, 1.5b\\)\n\
+- `/export Optional<json/csv>` → Get the alerts of this chat as a file\n\
+- `/import` → Reply to an exported file to add its alerts here, existing ones are skipped\n\
+- `/sync from \\[CHAT_ID\\]` → Copy the alerts of another chat you administer here\n\
+- `/sync to \\[CHAT_ID\\] Optional<CHAT_ID ...>` → Copy the alerts of this chat to chats you administer\n\
+- `/manager add/remove Optional<USER_ID>` → In reply to a member, let them manage the alerts without being admin\n\
+- `/public` → \\(on/start\\)/\\(off/stop\\)  let everyone use /demands and /price\n\
+- `/price \\[TOKEN\\]` → Price, 24h change and market cap of a spot token or a perp\n\
 \n\
 __*Intervals:*__\n\
 - 15min/15m → Every 15 minutes\n\
@@ -208,5 +638,8 @@ __*Intervals:*__\n\
 __*Examples:*__\n\
 `/setalert WAGMI 15min 3` → Alert on 3% WAGMI changes\n\
 `/setalert WAGMI 1h` → Track all WAGMI price updates in 1H\n\
+`/funding BTC above 50` → Alert when BTC funding goes above 50% annualized\n\
+`/oi ETH 1h 10` → Alert on 10% ETH open interest changes in 1H\n\
+`/depth WAGMI 5` → Alert when WAGMI bid depth drops below 5k$\n\
 \n\
-__*Note:*__ Only admins can use commands. Set percentage to 0 or omit for all price updates.";
+__*Note:*__ Only admins and alert managers can use commands. Set percentage to 0 or omit for all price updates.";
@@ -1,25 +1,30 @@
-use crate::{db::services::chat::insert_chat, global_data::MY_ID};
+use crate::{context::AppContext, db::services::chat::insert_chat};
 use teloxide::prelude::*;
 
-pub async fn handle_new_chat_members(bot: Bot, msg: &Message) -> anyhow::Result<Option<()>> {
+pub async fn handle_new_chat_members(
+    ctx: &AppContext,
+    msg: &Message,
+) -> anyhow::Result<Option<()>> {
     if let Some(new_members) = msg.new_chat_members() {
         // Check if the bot is among the new members
-        let bot_was_added = new_members.iter().any(|user| user.id == *MY_ID);
+        let bot_was_added = new_members.iter().any(|user| user.id == ctx.bot_id);
 
         if bot_was_added {
             // The bot was added to a group
             let chat_id = msg.chat.id;
-            insert_chat(chat_id.0).await?;
+            insert_chat(&ctx.pool, chat_id.0).await?;
 
             // Send a welcome message to the group
-            bot.send_message(chat_id, INVITED_MESSAGE).await?;
+            ctx.messenger
+                .send_text(chat_id, INVITED_MESSAGE.to_owned(), None, None)
+                .await?;
 
             // Perform any additional initialization here
             info!("Bot added to group: {:?}", chat_id);
             return Ok(Some(()));
         }
     }
-    return Ok(None);
+    Ok(None)
 }
 
 pub const INVITED_MESSAGE: &str =
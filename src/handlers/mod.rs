@@ -1,3 +1,6 @@
 pub mod callback;
 pub mod commands;
 pub mod invites;
+pub mod operator;
+pub mod permissions;
+pub mod transfer;
@@ -0,0 +1,245 @@
+use anyhow::anyhow;
+use std::cmp::Reverse;
+use teloxide::{prelude::*, utils::command::BotCommands};
+
+use crate::{
+    bot::{broadcast_message, send_error, send_message, utils::escape_user_text},
+    context::AppContext,
+    db::services::{
+        chat::fetch_all_chat_ids,
+        demands::{
+            delete_demand_by_composite_id, delete_demands_for_chat, format_demand_for_message,
+            get_demands_by_chat_id,
+        },
+        errors::fetch_error_reference,
+    },
+    state::{update_perp_data, update_token_data},
+    types::commands::OperatorCommand,
+};
+
+pub async fn operator_handler(
+    ctx: AppContext,
+    message: Message,
+    command: OperatorCommand,
+) -> anyhow::Result<()> {
+    // The branch filter already checks it, keep the handler safe on its own
+    if !message
+        .from
+        .as_ref()
+        .is_some_and(|user| ctx.is_operator(user.id))
+    {
+        return Ok(());
+    }
+    let chat_id = message.chat.id;
+
+    let result = match command {
+        OperatorCommand::Chats => handle_chats(&ctx).await,
+        OperatorCommand::ChatDemands { str } => handle_chat_demands(&ctx, str).await,
+        OperatorCommand::DeleteDemand { str } => handle_delete_demand(&ctx, str).await,
+        OperatorCommand::ClearDemands { str } => handle_clear_demands(&ctx, str).await,
+        OperatorCommand::Refresh => handle_refresh(&ctx).await,
+        OperatorCommand::Pause => {
+            ctx.status.set_paused(true);
+            Ok("Scheduled jobs paused".to_string())
+        }
+        OperatorCommand::Resume => {
+            ctx.status.set_paused(false);
+            Ok("Scheduled jobs resumed".to_string())
+        }
+        OperatorCommand::Status => Ok(handle_status(&ctx).await),
+        OperatorCommand::Broadcast { str } => handle_broadcast(&ctx, str).await,
+        OperatorCommand::Errors => Ok(handle_errors(&ctx).await),
+        OperatorCommand::ErrorRef { str } => handle_error_ref(&ctx, str).await,
+        OperatorCommand::OpHelp => Ok(escape_user_text(
+            &OperatorCommand::descriptions().to_string(),
+        )),
+    };
+
+    match result {
+        Ok(msg) => send_message(&ctx, chat_id, &msg, None),
+        Err(e) => {
+            error!("Operator command error: {:?}", e);
+            send_error(&ctx, chat_id, &escape_user_text(&e.to_string()), None);
+        }
+    }
+    Ok(())
+}
+
+fn parse_chat_id(input: &str) -> anyhow::Result<i64> {
+    input
+        .trim()
+        .parse()
+        .map_err(|_| anyhow!("Expected a chat id, got '{}'", input.trim()))
+}
+
+async fn handle_chats(ctx: &AppContext) -> anyhow::Result<String> {
+    let chat_ids = fetch_all_chat_ids(&ctx.pool).await?;
+    let counts = ctx.chat_demands.all().await;
+
+    let mut msg = format!("__*{} chats*__:\n", chat_ids.len());
+    for chat_id in chat_ids {
+        msg.push_str(&format!(
+            "`{}`: {} demands\n",
+            chat_id,
+            counts.get(&chat_id).cloned().unwrap_or(0)
+        ));
+    }
+    Ok(msg)
+}
+
+async fn handle_chat_demands(ctx: &AppContext, input: String) -> anyhow::Result<String> {
+    let chat_id = parse_chat_id(&input)?;
+    let demands = get_demands_by_chat_id(&ctx.pool, chat_id).await?;
+    if demands.is_empty() {
+        return Ok(format!("No demand for `{}`", chat_id));
+    }
+
+    let token_map = ctx.market.tokens().await;
+    let mut msg = format!("__*Demands of `{}`*__:\n", chat_id);
+    for demand in demands.iter() {
+        msg.push_str(&format!(
+            "{}\n`{}`\n",
+            format_demand_for_message(ctx, demand, &token_map),
+            demand.get_composite_id()
+        ));
+    }
+    msg.push_str("\nDelete one with /deletedemand ID");
+    Ok(msg)
+}
+
+async fn handle_delete_demand(ctx: &AppContext, input: String) -> anyhow::Result<String> {
+    delete_demand_by_composite_id(ctx, input.trim()).await?;
+    Ok("Demand erased".to_string())
+}
+
+async fn handle_clear_demands(ctx: &AppContext, input: String) -> anyhow::Result<String> {
+    let chat_id = parse_chat_id(&input)?;
+    delete_demands_for_chat(ctx, chat_id).await?;
+    Ok(format!("All demands of `{}` deleted", chat_id))
+}
+
+async fn handle_refresh(ctx: &AppContext) -> anyhow::Result<String> {
+    let mut msg = String::from("Refresh done:\n");
+    match update_token_data(ctx).await {
+        Ok(report) => msg.push_str(&format!(
+            "Spot: {}/{} items ok\n",
+            report.total - report.failed.len(),
+            report.total
+        )),
+        Err(e) => msg.push_str(&format!(
+            "Spot failed: {}\n",
+            escape_user_text(&e.to_string())
+        )),
+    }
+    match update_perp_data(ctx).await {
+        Ok(report) => msg.push_str(&format!(
+            "Perp: {}/{} items ok\n",
+            report.total - report.failed.len(),
+            report.total
+        )),
+        Err(e) => msg.push_str(&format!(
+            "Perp failed: {}\n",
+            escape_user_text(&e.to_string())
+        )),
+    }
+    match ctx.chat_demands.reload(&ctx.pool).await {
+        Ok(()) => msg.push_str("Demand counts reloaded\n"),
+        Err(e) => msg.push_str(&format!(
+            "Demand counts failed: {}\n",
+            escape_user_text(&e.to_string())
+        )),
+    }
+    Ok(msg)
+}
+
+async fn handle_status(ctx: &AppContext) -> String {
+    let now = ctx.clock.now().timestamp();
+    let mut msg = String::from("__*Status*__:\n");
+    if ctx.status.is_paused() {
+        msg.push_str("⏸ Scheduled jobs paused\n");
+    }
+
+    match ctx.status.last_run().await {
+        Some(run) => {
+            msg.push_str(&format!(
+                "Last run {}s ago for {} in {}ms: ",
+                now - run.started_at,
+                escape_user_text(&run.times.join(", ")),
+                run.duration_ms
+            ));
+            match run.error {
+                Some(e) => msg.push_str(&format!("❌ {}\n", escape_user_text(&e))),
+                None => msg.push_str("✅\n"),
+            }
+        }
+        None => msg.push_str("No run yet\n"),
+    }
+
+    let mut health: Vec<_> = ctx.status.fetch_health().await.into_iter().collect();
+    health.sort_by_key(|(source, _)| *source);
+    for (source, health) in health {
+        msg.push_str(&format!(
+            "{} fetch {}s ago: {} failed out of {}",
+            source,
+            now - health.fetched_at,
+            health.failed,
+            health.total
+        ));
+        if let Some(e) = health.error {
+            msg.push_str(&format!(" ❌ {}", escape_user_text(&e)));
+        }
+        msg.push('\n');
+    }
+    msg
+}
+
+async fn handle_broadcast(ctx: &AppContext, input: String) -> anyhow::Result<String> {
+    let text = input.trim();
+    if text.is_empty() {
+        return Err(anyhow!("/broadcast <message>"));
+    }
+    let chat_ids = fetch_all_chat_ids(&ctx.pool).await?;
+    let count = chat_ids.len();
+    broadcast_message(
+        ctx,
+        chat_ids,
+        format!("📢 {}", escape_user_text(text)),
+        "announcement",
+    )
+    .await?;
+    Ok(format!("Announcement sent to {} chats", count))
+}
+
+async fn handle_errors(ctx: &AppContext) -> String {
+    let now = ctx.clock.now().timestamp();
+    let mut groups: Vec<_> = ctx.error_groups.lock().await.clone().into_iter().collect();
+    if groups.is_empty() {
+        return "No error recorded".to_string();
+    }
+    groups.sort_by_key(|(_, group)| Reverse(group.last_seen));
+
+    let mut msg = String::from("__*Errors by kind*__:\n");
+    for (kind, group) in groups {
+        msg.push_str(&format!(
+            "{}× {}s ago: {}\n",
+            group.count,
+            now - group.last_seen,
+            escape_user_text(&kind)
+        ));
+    }
+    msg
+}
+
+async fn handle_error_ref(ctx: &AppContext, input: String) -> anyhow::Result<String> {
+    let reference = input.trim();
+    match fetch_error_reference(&ctx.pool, reference).await? {
+        Some(error_ref) => Ok(format!(
+            "Ref `{}` for user `{}`, {}s ago:\n{}",
+            escape_user_text(&error_ref.reference),
+            error_ref.user_id,
+            ctx.clock.now().timestamp() - error_ref.created_at,
+            escape_user_text(&error_ref.message)
+        )),
+        None => Err(anyhow!("No error with ref {}", reference)),
+    }
+}
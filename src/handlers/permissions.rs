@@ -0,0 +1,173 @@
+// src/handlers/permissions.rs
+
+use teloxide::{
+    prelude::*,
+    types::{ChatMemberUpdated, UserId},
+};
+
+use crate::{
+    context::AppContext,
+    db::services::{
+        chat::{fetch_public_commands, set_public_commands},
+        managers::{add_chat_manager, fetch_chat_managers, remove_chat_manager},
+    },
+    error::{BotError, BotResult},
+    state::ChatPermissions,
+    types::commands::{parse_manager, switch_type, ManagerAction},
+};
+
+pub const ADMIN_ONLY_ERR: &str = "Only the chat administrators and alert managers can do this";
+const MANAGER_ADMIN_ONLY_ERR: &str = "Only the chat administrators can choose the alert managers";
+
+/// Administrators and managers of the chat, from the cache when fresh
+async fn chat_permissions(ctx: &AppContext, chat_id: ChatId) -> BotResult<ChatPermissions> {
+    let now = ctx.clock.now().timestamp();
+    let ttl = ctx.config.permissions.admin_cache_ttl_secs;
+    if let Some(permissions) = ctx.permissions.get(chat_id.0, now, ttl).await {
+        return Ok(permissions);
+    }
+
+    let admins = ctx.bot.get_chat_administrators(chat_id).await?;
+    debug!("Admins of {}: {:?}", chat_id, admins);
+    let managers = fetch_chat_managers(&ctx.pool, chat_id.0)
+        .await
+        .map_err(BotError::Database)?;
+    let public_commands = fetch_public_commands(&ctx.pool, chat_id.0)
+        .await
+        .map_err(BotError::Database)?;
+    let permissions = ChatPermissions {
+        fetched_at: now,
+        owner: admins
+            .iter()
+            .find(|admin| admin.is_owner())
+            .map(|admin| admin.user.id),
+        admins: admins.iter().map(|admin| admin.user.id).collect(),
+        managers: managers.into_iter().map(|id| UserId(id as u64)).collect(),
+        public_commands,
+    };
+    ctx.permissions.insert(chat_id.0, permissions.clone()).await;
+    Ok(permissions)
+}
+
+pub async fn is_chat_admin(ctx: &AppContext, chat_id: ChatId, user_id: UserId) -> BotResult<bool> {
+    Ok(chat_permissions(ctx, chat_id)
+        .await?
+        .admins
+        .contains(&user_id))
+}
+
+/// Set alerts and delete them: see `PermissionsConfig`
+pub async fn can_manage_alerts(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    user_id: UserId,
+) -> BotResult<bool> {
+    let config = &ctx.config.permissions;
+    if config.is_manager(chat_id.0, user_id.0) {
+        return Ok(true);
+    }
+    let permissions = chat_permissions(ctx, chat_id).await?;
+    Ok(permissions.owner == Some(user_id)
+        || permissions.managers.contains(&user_id)
+        || (config.admins_manage_alerts && permissions.admins.contains(&user_id)))
+}
+
+/// Set with `/public`, see `Command::is_read_only`
+pub async fn allows_public_commands(ctx: &AppContext, chat_id: ChatId) -> BotResult<bool> {
+    Ok(chat_permissions(ctx, chat_id).await?.public_commands)
+}
+
+pub async fn handle_public_command(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    switch: String,
+) -> BotResult<String> {
+    let public_commands = switch_type(switch, "public").map_err(BotError::from_parser)?;
+    set_public_commands(&ctx.pool, chat_id.0, public_commands)
+        .await
+        .map_err(BotError::Database)?;
+    ctx.permissions.invalidate(chat_id.0).await;
+    if public_commands {
+        Ok("Everyone can now use /demands and /price here".to_string())
+    } else {
+        Ok("Only the administrators and alert managers can use commands here".to_string())
+    }
+}
+
+/// Promotions, demotions and departures change who can manage the alerts
+pub async fn chat_member_handler(ctx: AppContext, update: ChatMemberUpdated) -> anyhow::Result<()> {
+    debug!("Chat member update in {}", update.chat.id);
+    ctx.permissions.invalidate(update.chat.id.0).await;
+    Ok(())
+}
+
+/// `/manager add|remove` in reply to the user or with their ID, `/manager` lists them.
+/// Only administrators choose the managers, a manager can't add another.
+pub async fn handle_manager_command(
+    ctx: &AppContext,
+    message: &Message,
+    input: String,
+) -> BotResult<String> {
+    let chat_id = message.chat.id;
+    let (action, user_id) = parse_manager(input).map_err(BotError::from_parser)?;
+
+    if action == ManagerAction::List {
+        let managers = chat_permissions(ctx, chat_id).await?.managers;
+        if managers.is_empty() {
+            return Ok("No alert manager, only the administrators manage the alerts".to_string());
+        }
+        let mut ids: Vec<u64> = managers.iter().map(|id| id.0).collect();
+        ids.sort_unstable();
+        return Ok(format!(
+            "Alert managers: {}",
+            ids.iter()
+                .map(|id| format!("`{}`", id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    let sender = message
+        .from
+        .as_ref()
+        .map(|user| user.id)
+        .ok_or_else(|| BotError::Permission(MANAGER_ADMIN_ONLY_ERR.to_string()))?;
+    if !is_chat_admin(ctx, chat_id, sender).await? {
+        return Err(BotError::Permission(MANAGER_ADMIN_ONLY_ERR.to_string()));
+    }
+    let user_id = user_id
+        .map(UserId)
+        .or_else(|| {
+            message
+                .reply_to_message()
+                .and_then(|reply| reply.from.as_ref())
+                .map(|user| user.id)
+        })
+        .ok_or_else(|| BotError::user_input("Reply to the user or give their ID"))?;
+
+    let reply = match action {
+        ManagerAction::Add => {
+            let added = add_chat_manager(&ctx.pool, chat_id.0, user_id.0 as i64, sender.0 as i64)
+                .await
+                .map_err(BotError::Database)?;
+            if added {
+                format!("`{}` can now manage the alerts", user_id.0)
+            } else {
+                format!("`{}` already manages the alerts", user_id.0)
+            }
+        }
+        ManagerAction::Remove => {
+            let removed = remove_chat_manager(&ctx.pool, chat_id.0, user_id.0 as i64)
+                .await
+                .map_err(BotError::Database)?;
+            if removed {
+                format!("`{}` no longer manages the alerts", user_id.0)
+            } else {
+                format!("`{}` wasn't an alert manager", user_id.0)
+            }
+        }
+        ManagerAction::List => unreachable!("listed above"),
+    };
+    ctx.permissions.invalidate(chat_id.0).await;
+    Ok(reply)
+}
@@ -0,0 +1,482 @@
+// src/handlers/transfer.rs
+
+use std::collections::HashSet;
+
+use teloxide::{
+    prelude::*,
+    types::{ThreadId, UserId},
+};
+
+use crate::{
+    bot::{send_document, send_error_to_moderator, utils::escape_user_text},
+    constants::schedules::parse_interval,
+    context::AppContext,
+    db::services::demands::{get_demands_by_chat_id, Demand},
+    error::{BotError, BotResult},
+    handlers::{
+        commands::is_operator_chat,
+        permissions::{is_chat_admin, ADMIN_ONLY_ERR},
+    },
+    hyperliquid::{
+        fetch_price::SpotUniverse,
+        token_lookup::{lookup_token, TokenLookup},
+    },
+    state::{PerpMapping, TokenMapping},
+    types::{
+        commands::{
+            check_mcap_level, parse_sync, parse_watch, SyncDirection, ALERT, DEPTH, FUNDING_ABOVE,
+            FUNDING_BELOW, FUNDING_FLIP, LISTING, MCAP, OI_CHANGE, SPECIAL, SPREAD, WATCH,
+        },
+        demand_file::{decode, encode, DemandFileFormat, DemandRow},
+    },
+};
+
+const MAX_IMPORT_FILE_SIZE: u32 = 64 * 1024;
+const MAX_LISTED_ROW_ERRORS: usize = 10;
+
+const IMPORT_USAGE: &str = "Reply to a .json or .csv file made by /export with /import";
+
+pub async fn handle_export_command(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    thread_id: Option<ThreadId>,
+    format: String,
+) -> BotResult<String> {
+    let format = DemandFileFormat::parse(&format)
+        .ok_or_else(|| BotError::user_input("/export json or /export csv"))?;
+    let demands = get_demands_by_chat_id(&ctx.pool, chat_id.0)
+        .await
+        .map_err(BotError::Database)?;
+    if demands.is_empty() {
+        return Err(BotError::user_input("No demand to export"));
+    }
+
+    let rows = demand_rows(demands, &ctx.market.tokens().await);
+    let content = encode(&rows, format).map_err(BotError::Database)?;
+
+    send_document(
+        ctx,
+        chat_id,
+        format!("demands_{}.{}", chat_id.0, format.extension()),
+        content,
+        thread_id,
+    );
+    Ok(String::new())
+}
+
+/// Every row is checked before anything is inserted, a single invalid row aborts the import.
+/// Imported demands post to the thread the command was sent in.
+pub async fn handle_import_command(ctx: &AppContext, message: &Message) -> BotResult<String> {
+    let chat_id = message.chat.id;
+    let document = message
+        .reply_to_message()
+        .and_then(|reply| reply.document())
+        .ok_or_else(|| BotError::user_input(IMPORT_USAGE))?;
+    let format = document
+        .file_name
+        .as_deref()
+        .and_then(DemandFileFormat::from_file_name)
+        .ok_or_else(|| BotError::user_input(IMPORT_USAGE))?;
+    if document.file.size > MAX_IMPORT_FILE_SIZE {
+        return Err(BotError::user_input(format!(
+            "The file is too big, {}kB at most",
+            MAX_IMPORT_FILE_SIZE / 1024
+        )));
+    }
+
+    let content = ctx
+        .messenger
+        .fetch_file(document.file.id.clone())
+        .await
+        .map_err(BotError::Upstream)?;
+    let rows = decode(&content, format)
+        .map_err(|e| BotError::user_input(escape_user_text(&e.to_string())))?;
+    if rows.is_empty() {
+        return Err(BotError::user_input("The file has no demand"));
+    }
+
+    let demands = validate_rows(
+        rows,
+        &ctx.market.universe().await,
+        &ctx.market.perps().await,
+        chat_id.0,
+        message.thread_id.map(|id| id.0 .0),
+    )
+    .map_err(|e| BotError::user_input(escape_user_text(&e)))?;
+
+    let (imported, skipped) = insert_new_demands(ctx, chat_id, demands).await?;
+    Ok(format!(
+        "{} demands imported, {} already set",
+        imported, skipped
+    ))
+}
+
+/// `/sync from CHAT` copies into this chat, `/sync to CHAT...` copies this chat to the others.
+/// The sender must administer every chat involved, each target gets its own result line.
+pub async fn handle_sync_command(
+    ctx: &AppContext,
+    message: &Message,
+    input: String,
+) -> BotResult<String> {
+    let chat_id = message.chat.id;
+    let user_id = message
+        .from
+        .as_ref()
+        .map(|user| user.id)
+        .ok_or_else(|| BotError::Permission(ADMIN_ONLY_ERR.to_string()))?;
+    let (direction, chats) = parse_sync(input).map_err(BotError::from_parser)?;
+    if chats.contains(&chat_id) {
+        return Err(BotError::user_input("Give other chats than this one"));
+    }
+    // Managers can set alerts here, copying takes the administrator rights
+    check_chat_admin(ctx, chat_id, user_id).await?;
+
+    match direction {
+        SyncDirection::From => {
+            let source = chats[0];
+            check_chat_admin(ctx, source, user_id).await?;
+            let demands = source_demands(ctx, source).await?;
+            let (copied, skipped) = insert_new_demands(
+                ctx,
+                chat_id,
+                move_demands(demands, chat_id, message.thread_id),
+            )
+            .await?;
+            Ok(format!(
+                "{} demands copied from `{}`, {} already set",
+                copied, source, skipped
+            ))
+        }
+        SyncDirection::To => {
+            let demands = source_demands(ctx, chat_id).await?;
+            let mut lines = Vec::new();
+            for target in chats {
+                let result = match check_chat_admin(ctx, target, user_id).await {
+                    Ok(()) => {
+                        insert_new_demands(ctx, target, move_demands(demands.clone(), target, None))
+                            .await
+                    }
+                    Err(e) => Err(e),
+                };
+                let line = match result {
+                    Ok((copied, skipped)) => {
+                        format!("{} demands copied, {} already set", copied, skipped)
+                    }
+                    Err(e) => {
+                        if e.is_internal() {
+                            send_error_to_moderator(
+                                ctx,
+                                format!("{} syncing chat {}: {:?}", e, target, e),
+                            );
+                        }
+                        e.user_message().to_owned()
+                    }
+                };
+                lines.push(format!("`{}`: {}", target, line));
+            }
+            Ok(format!("Sync results:\n{}", lines.join("\n")))
+        }
+    }
+}
+
+/// The bot must be in the chat to list its admins
+async fn check_chat_admin(ctx: &AppContext, chat_id: ChatId, user_id: UserId) -> BotResult<()> {
+    match is_chat_admin(ctx, chat_id, user_id).await {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(BotError::Permission(format!(
+            "You are not an administrator of `{}`",
+            chat_id
+        ))),
+        Err(BotError::Upstream(e)) => {
+            debug!("No admin list for chat {}: {}", chat_id, e);
+            Err(BotError::user_input(format!(
+                "The bot cannot see the administrators of `{}`, is it a member?",
+                chat_id
+            )))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn source_demands(ctx: &AppContext, chat_id: ChatId) -> BotResult<Vec<Demand>> {
+    let demands = get_demands_by_chat_id(&ctx.pool, chat_id.0)
+        .await
+        .map_err(BotError::Database)?;
+    if demands.is_empty() {
+        return Err(BotError::user_input(format!("No demand in `{}`", chat_id)));
+    }
+    Ok(demands)
+}
+
+/// Threads belong to their chat, copies post to the given one
+fn move_demands(demands: Vec<Demand>, chat_id: ChatId, thread_id: Option<ThreadId>) -> Vec<Demand> {
+    demands
+        .into_iter()
+        .map(|demand| Demand {
+            chat_id: chat_id.0,
+            thread_id: thread_id.map(|id| id.0 .0),
+            ..demand
+        })
+        .collect()
+}
+
+/// Skips the demands already set, or given twice, then checks the quota before inserting any.
+/// Returns the inserted and skipped counts.
+async fn insert_new_demands(
+    ctx: &AppContext,
+    chat_id: ChatId,
+    mut demands: Vec<Demand>,
+) -> BotResult<(usize, usize)> {
+    let mut existing: HashSet<String> = get_demands_by_chat_id(&ctx.pool, chat_id.0)
+        .await
+        .map_err(BotError::Database)?
+        .iter()
+        .map(Demand::get_composite_id)
+        .collect();
+    let total = demands.len();
+    demands.retain(|demand| existing.insert(demand.get_composite_id()));
+    let skipped = total - demands.len();
+
+    let count = ctx.chat_demands.get(chat_id.0).await as usize;
+    let max = ctx.config.quotas.max_demands_per_chat as usize;
+    if !is_operator_chat(ctx, &chat_id) && count + demands.len() > max {
+        return Err(BotError::Quota(format!(
+            "Nothing added: {} new demands but only {} left in the quota",
+            demands.len(),
+            max.saturating_sub(count)
+        )));
+    }
+
+    let inserted = demands.len();
+    for demand in demands {
+        demand.insert_to_db(ctx).await?;
+    }
+    Ok((inserted, skipped))
+}
+
+/// Rows of an export, spot tokens are named from `token_map`
+pub fn demand_rows(demands: Vec<Demand>, token_map: &TokenMapping) -> Vec<DemandRow> {
+    demands
+        .into_iter()
+        .map(|demand| {
+            let name = match demand.type_of.as_str() {
+                ALERT | SPREAD | DEPTH | MCAP => token_map
+                    .get(&demand.token)
+                    .map(|info| info.name.clone())
+                    .unwrap_or_default(),
+                _ => demand.token.clone(),
+            };
+            DemandRow::from_demand(demand, name)
+        })
+        .collect()
+}
+
+/// The demands of an import, or the list of invalid rows when there is any
+pub fn validate_rows(
+    rows: Vec<DemandRow>,
+    universe: &SpotUniverse,
+    perps: &PerpMapping,
+    chat_id: i64,
+    thread_id: Option<i32>,
+) -> Result<Vec<Demand>, String> {
+    let mut demands = Vec::new();
+    let mut errors = Vec::new();
+    for (i, row) in rows.into_iter().enumerate() {
+        match validate_row(row, universe, perps) {
+            Ok(row) => demands.push(row.into_demand(chat_id, thread_id)),
+            Err(e) => errors.push(format!("Row {}: {}", i + 1, e)),
+        }
+    }
+    if errors.is_empty() {
+        return Ok(demands);
+    }
+    let more = errors.len().saturating_sub(MAX_LISTED_ROW_ERRORS);
+    errors.truncate(MAX_LISTED_ROW_ERRORS);
+    if more > 0 {
+        errors.push(format!("and {} more", more));
+    }
+    Err(format!("Nothing imported:\n{}", errors.join("\n")))
+}
+
+/// Same checks as the commands setting each type, the token and interval come back normalized
+fn validate_row(
+    mut row: DemandRow,
+    universe: &SpotUniverse,
+    perps: &PerpMapping,
+) -> Result<DemandRow, String> {
+    match row.type_of.as_str() {
+        ALERT | SPREAD | DEPTH | MCAP => {
+            row.token = resolve_spot_token(&row, universe)?;
+        }
+        FUNDING_ABOVE | FUNDING_BELOW | FUNDING_FLIP | OI_CHANGE => {
+            row.token = row.token.to_ascii_uppercase();
+            if !perps.contains_key(&row.token) {
+                return Err(format!("perp '{}' doesn't exist", row.token));
+            }
+        }
+        WATCH => {
+            let (address, label) = parse_watch(format!(
+                "{} {}",
+                row.token,
+                row.label.as_deref().unwrap_or_default()
+            ))
+            .map_err(|e| e.to_string())?;
+            row.token = address;
+            row.label = label;
+        }
+        SPECIAL | LISTING => {
+            row.token = String::new();
+        }
+        other => return Err(format!("unknown type '{}'", other)),
+    }
+
+    match row.type_of.as_str() {
+        ALERT | OI_CHANGE => {
+            row.interval = parse_interval(&row.interval)
+                .ok_or_else(|| format!("invalid interval '{}'", row.interval))?
+                .to_owned();
+        }
+        _ => row.interval = String::new(),
+    }
+
+    if row.type_of == MCAP {
+        check_mcap_level(row.threshold_usd as f64).map_err(|e| e.to_string())?;
+    } else if row.threshold_usd != 0 {
+        return Err("only mcap takes a threshold_usd".to_owned());
+    }
+
+    match row.type_of.as_str() {
+        OI_CHANGE | SPREAD | DEPTH if row.percentage <= 0 => {
+            Err("the threshold must be above 0".to_owned())
+        }
+        FUNDING_FLIP | SPECIAL | LISTING | WATCH | MCAP if row.percentage != 0 => {
+            Err("this type takes no percentage".to_owned())
+        }
+        _ => Ok(row),
+    }
+}
+
+/// Exports store the token index, a hand written file can give a name instead
+fn resolve_spot_token(row: &DemandRow, universe: &SpotUniverse) -> Result<String, String> {
+    let query = if row.token.is_empty() {
+        &row.name
+    } else {
+        &row.token
+    };
+    if let Ok(index) = query.parse::<usize>() {
+        if universe.tokens.iter().any(|token| token.index == index) {
+            return Ok(index.to_string());
+        }
+        return Err(format!("token #{} doesn't exist", index));
+    }
+    match lookup_token(universe, query) {
+        TokenLookup::Found(token) => Ok(token.index.to_string()),
+        TokenLookup::Ambiguous(_) => Err(format!(
+            "several tokens match '{}', use the token index",
+            query
+        )),
+        TokenLookup::NotFound { .. } => Err(format!("token '{}' doesn't exist", query)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperliquid::{fetch_price::test_support::universe, perp_data::PerpInfo};
+
+    fn perps() -> PerpMapping {
+        PerpMapping::from([(
+            "BTC".to_owned(),
+            PerpInfo {
+                name: "BTC".to_owned(),
+                funding: 0.0,
+                open_interest: 0.0,
+                mark_price: 0.0,
+            },
+        )])
+    }
+
+    fn row(type_of: &str, token: &str, percentage: i16, interval: &str) -> DemandRow {
+        DemandRow {
+            type_of: type_of.to_owned(),
+            token: token.to_owned(),
+            percentage,
+            interval: interval.to_owned(),
+            ..DemandRow::default()
+        }
+    }
+
+    fn validate(row: DemandRow) -> Result<DemandRow, String> {
+        validate_row(row, &universe(), &perps())
+    }
+
+    #[test]
+    fn validate_row_normalizes_tokens_and_intervals() {
+        let alert = validate(row(ALERT, "purre", 5, "hourly")).unwrap();
+        assert_eq!((alert.token.as_str(), alert.interval.as_str()), ("2", "1h"));
+
+        let by_name = validate(DemandRow {
+            name: "PURRE".to_owned(),
+            ..row(SPREAD, "", 30, "")
+        })
+        .unwrap();
+        assert_eq!(by_name.token, "2");
+
+        let oi = validate(row(OI_CHANGE, "btc", 10, "15m")).unwrap();
+        assert_eq!((oi.token.as_str(), oi.interval.as_str()), ("BTC", "15min"));
+
+        let listing = validate(row(LISTING, "anything", 0, "1h")).unwrap();
+        assert_eq!(
+            (listing.token.as_str(), listing.interval.as_str()),
+            ("", "")
+        );
+    }
+
+    #[test]
+    fn validate_row_rejects_invalid_rows() {
+        assert!(validate(row("price", "1", 5, "1h")).is_err());
+        assert!(validate(row(ALERT, "9", 5, "1h")).is_err());
+        assert!(validate(row(ALERT, "1", 5, "2h")).is_err());
+        assert!(validate(row(FUNDING_FLIP, "ETH", 0, "")).is_err());
+        assert!(validate(row(FUNDING_FLIP, "BTC", 5, "")).is_err());
+        assert!(validate(row(DEPTH, "1", 0, "")).is_err());
+        assert!(validate(DemandRow {
+            threshold_usd: 500,
+            ..row(MCAP, "1", 0, "")
+        })
+        .is_err());
+        assert!(validate(DemandRow {
+            threshold_usd: 20_000_000_000_000,
+            ..row(MCAP, "1", 0, "")
+        })
+        .is_err());
+        assert!(validate(row(WATCH, "0x1234", 0, "")).is_err());
+        assert!(validate(DemandRow {
+            threshold_usd: 1_000,
+            ..row(ALERT, "1", 5, "1h")
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn validate_rows_lists_every_invalid_row() {
+        let rows = vec![
+            row(ALERT, "1", 5, "1h"),
+            row(ALERT, "9", 5, "1h"),
+            row("price", "1", 5, "1h"),
+        ];
+        let error = validate_rows(rows, &universe(), &perps(), -1001, None).unwrap_err();
+        assert!(error.contains("Row 2: token #9 doesn't exist"), "{}", error);
+        assert!(error.contains("Row 3: unknown type 'price'"), "{}", error);
+
+        let demands = validate_rows(
+            vec![row(ALERT, "1", 5, "1h")],
+            &universe(),
+            &perps(),
+            -1001,
+            Some(7),
+        )
+        .unwrap();
+        assert_eq!((demands[0].chat_id, demands[0].thread_id), (-1001, Some(7)));
+    }
+}
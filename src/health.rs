@@ -0,0 +1,77 @@
+// src/health.rs
+
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::{
+    constants::health::{
+        DB_CHECK_TIMEOUT_SECS, FETCH_MAX_AGE_SECS, HEARTBEAT_MAX_AGE_SECS, RUN_MAX_AGE_SECS,
+    },
+    context::AppContext,
+};
+
+/// Liveness: the dispatcher is running and the scheduler still fires jobs
+pub fn liveness(ctx: &AppContext) -> (bool, Value) {
+    let now = ctx.clock.now().timestamp();
+    let dispatcher = ctx.status.dispatcher_running();
+    let heartbeat = ctx.status.heartbeat();
+    // The first heartbeat can take a few seconds after startup
+    let scheduler = now - heartbeat.max(ctx.status.started_at) < HEARTBEAT_MAX_AGE_SECS;
+
+    (
+        dispatcher && scheduler,
+        json!({
+            "dispatcher": dispatcher,
+            "scheduler": scheduler,
+            "scheduler_heartbeat": heartbeat,
+        }),
+    )
+}
+
+/// Readiness: database reachable, recent token data and a main sequence on time
+pub async fn readiness(ctx: &AppContext) -> (bool, Value) {
+    let now = ctx.clock.now().timestamp();
+    let in_grace = now - ctx.status.started_at < RUN_MAX_AGE_SECS;
+
+    let database = check_database(ctx).await;
+
+    let last_token_success = ctx
+        .status
+        .fetch_health()
+        .await
+        .get("spot")
+        .and_then(|health| health.last_success_at);
+    let token_data = last_token_success.is_some_and(|at| now - at < FETCH_MAX_AGE_SECS);
+
+    let paused = ctx.status.is_paused();
+    let last_run = ctx.status.last_run().await;
+    let last_run_end = last_run
+        .as_ref()
+        .map(|run| run.started_at + run.duration_ms / 1000);
+    let sequence = paused
+        || match last_run_end {
+            Some(end) => now - end < RUN_MAX_AGE_SECS,
+            None => in_grace,
+        };
+
+    (
+        database && token_data && sequence,
+        json!({
+            "database": database,
+            "token_data": token_data,
+            "last_token_fetch": last_token_success,
+            "sequence": sequence,
+            "scheduler_paused": paused,
+            "last_run_end": last_run_end,
+            "last_run_error": last_run.and_then(|run| run.error),
+        }),
+    )
+}
+
+async fn check_database(ctx: &AppContext) -> bool {
+    let query = sqlx::query("SELECT 1").execute(&ctx.pool);
+    matches!(
+        tokio::time::timeout(Duration::from_secs(DB_CHECK_TIMEOUT_SECS), query).await,
+        Ok(Ok(_))
+    )
+}
@@ -0,0 +1,64 @@
+// src/hyperliquid/error.rs
+
+use std::fmt;
+
+#[derive(Debug)]
+pub enum FetchError {
+    /// The whole request failed: network, HTTP status or body shape
+    Request(reqwest::Error),
+    /// One universe item could not be used, the rest of the fetch is fine
+    Asset { coin: String, reason: String },
+}
+
+impl FetchError {
+    pub fn asset(coin: impl Into<String>, reason: impl Into<String>) -> Self {
+        FetchError::Asset {
+            coin: coin.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for FetchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FetchError::Request(e) => write!(f, "Request failed: {}", e),
+            FetchError::Asset { coin, reason } => write!(f, "Asset {} skipped: {}", coin, reason),
+        }
+    }
+}
+
+impl std::error::Error for FetchError {}
+
+impl From<reqwest::Error> for FetchError {
+    fn from(err: reqwest::Error) -> Self {
+        FetchError::Request(err)
+    }
+}
+
+/// Outcome of a fetch where bad assets got skipped
+#[derive(Debug, Default)]
+pub struct FetchReport {
+    pub total: usize,
+    pub failed: Vec<FetchError>,
+}
+
+impl FetchReport {
+    pub fn push<T>(&mut self, result: Result<T, FetchError>) -> Option<T> {
+        self.total += 1;
+        match result {
+            Ok(value) => Some(value),
+            Err(e) => {
+                self.failed.push(e);
+                None
+            }
+        }
+    }
+}
+
+/// Parse a numeric field of an info response, with the asset as context
+pub fn parse_field(coin: &str, field: &str, value: &str) -> Result<f64, FetchError> {
+    value
+        .parse::<f64>()
+        .map_err(|e| FetchError::asset(coin, format!("{} '{}' {}", field, value, e)))
+}
@@ -2,105 +2,273 @@
 
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::collections::HashMap;
 
-use crate::global_data::TokenMapping;
+use crate::{
+    bot::utils::{escape_user_text, format_usd_compact},
+    hyperliquid::error::{parse_field, FetchError, FetchReport},
+    state::TokenMapping,
+};
+
+pub const USDC_INDEX: usize = 0;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TokenInfo {
     pub name: String,
     pub full_name: Option<String>,
-    pub price: f64,
+    pub price: f64, // USD, converted through the quote when it isn't USDC
     pub price_prev_24h: f64,
     pub pair_number: Option<u16>,
-    pub market_cap: u32,
+    pub market_cap: f64, // price * circulating supply
+    #[serde(default)]
+    pub fdv: Option<f64>, // price * max (or total) supply, once tokenDetails got fetched
+    #[serde(default)]
+    pub coin: String, // pair name on the info endpoint, `@N` or `PURR/USDC`
+    #[serde(default)]
+    pub token_id: String,
+    #[serde(default)]
+    pub pair: Option<SpotPair>, // None for snapshots stored before pairs
+    /// The same token on its other quotes, the USDC pair is the one above
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub other_pairs: Vec<TokenInfo>,
+}
+
+/// A spot pair, prices on it are expressed in the quote token
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct SpotPair {
+    pub index: u32,
+    pub base: usize,
+    pub quote: usize,
+    pub quote_name: String,
+    pub price_in_quote: f64,
+}
+
+impl TokenInfo {
+    /// Every pair of the token with the key of its pump cooldown, the main pair first
+    pub fn pairs<'a>(&'a self, key: &'a str) -> impl Iterator<Item = (&'a str, &'a TokenInfo)> {
+        std::iter::once((key, self)).chain(
+            self.other_pairs
+                .iter()
+                .map(|other| (other.coin.as_str(), other)),
+        )
+    }
+
+    /// "MC: 1.23M$ | FDV: 4.56M$" line shown in alert messages
+    pub fn format_caps(&self) -> String {
+        let mut caps = format!("MC: {}", format_usd_compact(self.market_cap));
+        if let Some(fdv) = self.fdv {
+            caps.push_str(&format!(" \\| FDV: {}", format_usd_compact(fdv)));
+        }
+        caps
+    }
+
+    /// "1.2$" or "1.2$ (0.05 HYPE)" when the pair isn't quoted in USDC
+    pub fn format_price(&self) -> String {
+        match &self.pair {
+            Some(pair) if pair.quote != USDC_INDEX => format!(
+                "{}$ \\({} {}\\)",
+                self.price,
+                pair.price_in_quote,
+                escape_user_text(&pair.quote_name)
+            ),
+            _ => format!("{}$", self.price),
+        }
+    }
+}
+
+/// Raw spot universe as returned by the info endpoint, kept to diff listings between fetches
+#[derive(Debug, Clone, Default)]
+pub struct SpotUniverse {
+    pub tokens: Vec<Token>,
+    pub pairs: Vec<UniverseItem>,
+    pub mark_prices: HashMap<String, f64>, // USD, by pair name
+    /// False when a token or pair of the meta was skipped, what's missing isn't delisted
+    pub complete: bool,
 }
 
-pub async fn fetch_token_data() -> anyhow::Result<(TokenMapping, Vec<String>)> {
+struct PairQuote {
+    pair: SpotPair,
+    coin: String,
+    prev_day_in_quote: f64,
+    circulating_supply: f64,
+}
+
+/// Tokens are keyed by their Hyperliquid token index, names are display only.
+/// A token quoted several times is priced by its USDC pair, the other quotes are converted
+/// to USD and kept in `other_pairs`.
+/// Malformed items are skipped and listed in the report instead of failing the fetch.
+pub async fn fetch_token_data(
+    info_url: &str,
+) -> Result<(TokenMapping, SpotUniverse, FetchReport), FetchError> {
     // Initialize the HTTP client
     let client = Client::new();
 
     // Send the POST request
     let (meta, market_data_array) = client
-        .post("https://api.hyperliquid.xyz/info")
+        .post(info_url)
         .json(&serde_json::json!({"type": "spotMetaAndAssetCtxs"}))
         .send()
         .await?
-        .json::<ApiResponse>()
+        .error_for_status()?
+        .json::<RawApiResponse>()
         .await?;
 
-    // Parse 'tokens' array
-    let tokens_array = meta.tokens;
-    let universe = meta.universe;
+    let mut report = FetchReport::default();
+
+    // Items are decoded one by one so a bad one doesn't drop the whole response
+    let tokens_array: Vec<Token> = meta
+        .tokens
+        .into_iter()
+        .filter_map(|raw| report.push(decode_item::<Token>(raw)))
+        .collect();
+    let universe: Vec<UniverseItem> = meta
+        .universe
+        .into_iter()
+        .filter_map(|raw| report.push(decode_item::<UniverseItem>(raw)))
+        .collect();
+    let complete = report.failed.is_empty();
 
     // Map from index to Token
 
-    let mut token_array: Vec<String> = Vec::new();
     let mut index_to_token_item: HashMap<usize, Token> = HashMap::new();
-    for token in tokens_array {
-        token_array.push(token.name.to_uppercase());
-        index_to_token_item.insert(token.index, token);
+    for token in tokens_array.iter() {
+        index_to_token_item.insert(token.index, token.clone());
     }
 
-    // Build the mapping and the array
-    let mut token_mapping: TokenMapping = HashMap::new();
+    // First pass: every pair with its price in quote
+    let mut quotes: Vec<PairQuote> = Vec::new();
+    for raw in market_data_array {
+        let quote = decode_item::<MarketDataItem>(raw)
+            .and_then(|item| parse_pair_quote(&item, &universe, &index_to_token_item));
+        if let Some(quote) = report.push(quote) {
+            quotes.push(quote);
+        }
+    }
 
-    for market_data_value in market_data_array.iter() {
-        let market_data_item: MarketDataItem = market_data_value.to_owned();
+    // USD price of the tokens quoted against USDC, used to convert the other quotes
+    let mut usd_prices: HashMap<usize, f64> = quotes
+        .iter()
+        .filter(|q| q.pair.quote == USDC_INDEX)
+        .map(|q| (q.pair.base, q.pair.price_in_quote))
+        .collect();
+    usd_prices.insert(USDC_INDEX, 1.0);
 
-        if let Some(pair) = universe.iter().find(|x| x.name == market_data_item.coin) {
-            let index = pair
-                .tokens
-                .iter()
-                .find(|&&x| x != 0)
-                .expect("Canno0t find this issue");
-            let index = index.to_owned() as usize;
-
-            if let Some(token_item) = index_to_token_item.get(&index) {
-                let name_upper = token_item.name.to_uppercase();
-                let full_name = token_item.full_name.clone();
-                let price: f64 = market_data_item.mark_px.parse()?;
-                let price_prev_24h: f64 = market_data_item.prev_day_px.parse()?;
-                let pair_number = transform_coin_to_pair_no(&market_data_item.coin);
-                let market_cap =
-                    (price * market_data_item.circulating_supply.parse::<f64>()?).round() as u32;
-
-                // Create the TokenInfo object
-                let token_info = TokenInfo {
-                    name: token_item.name.clone(),
-                    full_name,
-                    price,
-                    price_prev_24h,
-                    pair_number,
-                    market_cap,
-                };
-
-                // Insert into the mapping
-                token_mapping.insert(name_upper, token_info.clone());
+    // Second pass: every pair priced in USD, grouped by base token
+    let mut by_token: HashMap<usize, Vec<TokenInfo>> = HashMap::new();
+    let mut mark_prices: HashMap<String, f64> = HashMap::new();
+
+    for quote in quotes {
+        let quote_usd = match usd_prices.get(&quote.pair.quote) {
+            Some(price) => *price,
+            None => {
+                debug!("No USD price for the quote of {}", quote.coin);
+                continue;
             }
-        } else {
-            error!("Pair not found foe {:?}", market_data_value)
+        };
+        let price = quote.pair.price_in_quote * quote_usd;
+        mark_prices.insert(quote.coin.clone(), price);
+
+        if let Some(token_item) = index_to_token_item.get(&quote.pair.base) {
+            // Create the TokenInfo object
+            let token_info = TokenInfo {
+                name: token_item.name.clone(),
+                full_name: token_item.full_name.clone(),
+                price,
+                price_prev_24h: quote.prev_day_in_quote * quote_usd,
+                pair_number: pair_number(quote.pair.index),
+                market_cap: price * quote.circulating_supply,
+                fdv: None,
+                coin: quote.coin,
+                token_id: token_item.token_id.clone(),
+                pair: Some(quote.pair.clone()),
+                other_pairs: Vec::new(),
+            };
+            by_token
+                .entry(quote.pair.base)
+                .or_default()
+                .push(token_info);
         }
     }
 
-    // Return the mapping and array as a tuple
-    Ok((token_mapping, token_array))
+    let token_mapping: TokenMapping = by_token
+        .into_iter()
+        .map(|(base, mut pairs)| {
+            let main = pairs
+                .iter()
+                .position(|info| info.pair.as_ref().map(|pair| pair.quote) == Some(USDC_INDEX))
+                .unwrap_or(0);
+            let mut token_info = pairs.swap_remove(main);
+            token_info.other_pairs = pairs;
+            (base.to_string(), token_info)
+        })
+        .collect();
+
+    let spot_universe = SpotUniverse {
+        tokens: tokens_array,
+        pairs: universe,
+        mark_prices,
+        complete,
+    };
+
+    // Return the mapping, universe and skipped items
+    Ok((token_mapping, spot_universe, report))
 }
 
-fn transform_coin_to_pair_no(input: &str) -> Option<u16> {
-    match input {
-        "PURR/USDC" => Some(10_000),
-        _ => Some(
-            10_000
-                + input[1..]
-                    .parse::<u16>()
-                    .expect(&format!("ERROR IN INPUT transform_coin_to_pair_no{input}")),
-        ),
-    }
+fn decode_item<T: for<'de> Deserialize<'de>>(raw: Value) -> Result<T, FetchError> {
+    let coin = raw
+        .get("coin")
+        .or_else(|| raw.get("name"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("?")
+        .to_owned();
+    serde_json::from_value(raw).map_err(|e| FetchError::asset(coin, e.to_string()))
 }
 
-type ApiResponse = (ResponseMeta, Vec<MarketDataItem>);
+fn parse_pair_quote(
+    item: &MarketDataItem,
+    universe: &[UniverseItem],
+    index_to_token: &HashMap<usize, Token>,
+) -> Result<PairQuote, FetchError> {
+    let pair = universe
+        .iter()
+        .find(|x| x.name == item.coin)
+        .ok_or_else(|| FetchError::asset(&item.coin, "pair not in universe"))?;
+    let (base, quote) = match (pair.tokens.first(), pair.tokens.get(1)) {
+        (Some(base), Some(quote)) => (*base as usize, *quote as usize),
+        _ => return Err(FetchError::asset(&item.coin, "pair without base/quote")),
+    };
+    let quote_name = index_to_token
+        .get(&quote)
+        .map(|token| token.name.clone())
+        .unwrap_or_default();
+
+    Ok(PairQuote {
+        pair: SpotPair {
+            index: pair.index as u32,
+            base,
+            quote,
+            quote_name,
+            price_in_quote: parse_field(&item.coin, "markPx", &item.mark_px)?,
+        },
+        coin: item.coin.clone(),
+        prev_day_in_quote: parse_field(&item.coin, "prevDayPx", &item.prev_day_px)?,
+        circulating_supply: parse_field(&item.coin, "circulatingSupply", &item.circulating_supply)?,
+    })
+}
+
+/// Number used by the referral trade link, 10_000 + the spot pair index
+pub fn pair_number(pair_index: u32) -> Option<u16> {
+    u16::try_from(10_000 + pair_index).ok()
+}
+
+type RawApiResponse = (RawResponseMeta, Vec<Value>);
+#[derive(Debug, Deserialize)]
+struct RawResponseMeta {
+    universe: Vec<Value>,
+    tokens: Vec<Value>,
+}
 #[allow(dead_code)]
 #[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
@@ -120,7 +288,7 @@ pub struct ResponseMeta {
     pub tokens: Vec<Token>,
 }
 #[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Token {
     pub name: String,
@@ -133,7 +301,7 @@ pub struct Token {
     pub full_name: Option<String>,
 }
 #[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct UniverseItem {
     pub tokens: Vec<i32>,
@@ -141,3 +309,47 @@ pub struct UniverseItem {
     pub index: i32,
     pub is_canonical: bool,
 }
+
+/// Universe fixtures shared by the tests resolving tokens
+#[cfg(test)]
+pub mod test_support {
+    use super::{SpotUniverse, Token, UniverseItem};
+
+    pub fn token(index: usize, name: &str, full_name: Option<&str>) -> Token {
+        Token {
+            name: name.to_owned(),
+            sz_decimals: 2,
+            wei_decimals: 8,
+            index,
+            token_id: format!("0x{:032x}", index),
+            is_canonical: false,
+            evm_contract: None,
+            full_name: full_name.map(str::to_owned),
+        }
+    }
+
+    /// `base` quoted in USDC, the token 0
+    pub fn pair(index: i32, base: i32) -> UniverseItem {
+        UniverseItem {
+            tokens: vec![base, 0],
+            name: format!("@{}", index),
+            index,
+            is_canonical: false,
+        }
+    }
+
+    /// PURR and its twin PURRE trade, HFUN's full name is "purr", LOST has no pair
+    pub fn universe() -> SpotUniverse {
+        SpotUniverse {
+            tokens: vec![
+                token(0, "USDC", None),
+                token(1, "PURR", Some("Purr Cat")),
+                token(2, "PURRE", None),
+                token(3, "HFUN", Some("purr")),
+                token(4, "LOST", None),
+            ],
+            pairs: vec![pair(10, 1), pair(11, 2), pair(12, 3)],
+            ..SpotUniverse::default()
+        }
+    }
+}
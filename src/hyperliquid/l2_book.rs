@@ -0,0 +1,98 @@
+// src/hyperliquid/l2_book.rs
+
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::hyperliquid::error::FetchError;
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+pub struct BookLevel {
+    pub px: String,
+    pub sz: String,
+    pub n: u32,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+pub struct L2Book {
+    pub coin: String,
+    pub time: i64,
+    pub levels: Vec<Vec<BookLevel>>, // [bids, asks]
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LiquidityMetrics {
+    pub mid: f64,
+    pub spread_bps: f64,
+    pub bid_depth: f64, // USD within range below mid
+    pub ask_depth: f64, // USD within range above mid
+}
+
+impl LiquidityMetrics {
+    /// Books are in quote units, convert them with the USD price of the quote
+    pub fn in_usd(self, quote_usd: f64) -> Self {
+        Self {
+            mid: self.mid * quote_usd,
+            spread_bps: self.spread_bps,
+            bid_depth: self.bid_depth * quote_usd,
+            ask_depth: self.ask_depth * quote_usd,
+        }
+    }
+}
+
+pub async fn fetch_l2_book(info_url: &str, coin: &str) -> Result<L2Book, FetchError> {
+    let book = Client::new()
+        .post(info_url)
+        .json(&serde_json::json!({"type": "l2Book", "coin": coin}))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<L2Book>()
+        .await?;
+
+    Ok(book)
+}
+
+/// Spread and depth within `range_percent` of mid, None for a one sided book
+pub fn compute_liquidity(book: &L2Book, range_percent: f64) -> Option<LiquidityMetrics> {
+    let parse = |levels: Option<&Vec<BookLevel>>| -> Vec<(f64, f64)> {
+        levels
+            .map(|levels| {
+                levels
+                    .iter()
+                    .filter_map(|l| Some((l.px.parse().ok()?, l.sz.parse().ok()?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let bids = parse(book.levels.first());
+    let asks = parse(book.levels.get(1));
+
+    let best_bid = bids.first()?.0;
+    let best_ask = asks.first()?.0;
+    let mid = (best_bid + best_ask) / 2.0;
+    if mid <= 0.0 {
+        return None;
+    }
+
+    let low = mid * (1.0 - range_percent / 100.0);
+    let high = mid * (1.0 + range_percent / 100.0);
+    let bid_depth = bids
+        .iter()
+        .filter(|(px, _)| *px >= low)
+        .map(|(px, sz)| px * sz)
+        .sum();
+    let ask_depth = asks
+        .iter()
+        .filter(|(px, _)| *px <= high)
+        .map(|(px, sz)| px * sz)
+        .sum();
+
+    Some(LiquidityMetrics {
+        mid,
+        spread_bps: (best_ask - best_bid) / mid * 10_000.0,
+        bid_depth,
+        ask_depth,
+    })
+}
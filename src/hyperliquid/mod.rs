@@ -1,3 +1,9 @@
 // src/hyperliquid/mod.rs
 
+pub mod error;
 pub mod fetch_price;
+pub mod l2_book;
+pub mod perp_data;
+pub mod token_details;
+pub mod token_lookup;
+pub mod user_state;
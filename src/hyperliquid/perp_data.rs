@@ -0,0 +1,86 @@
+// src/hyperliquid/perp_data.rs
+
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::{
+    hyperliquid::error::{parse_field, FetchError, FetchReport},
+    state::PerpMapping,
+};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct PerpInfo {
+    pub name: String,
+    pub funding: f64, // hourly rate
+    pub open_interest: f64,
+    pub mark_price: f64,
+}
+
+impl PerpInfo {
+    pub fn annualized_funding(&self) -> f64 {
+        self.funding * 24.0 * 365.0 * 100.0
+    }
+}
+
+/// Malformed assets are skipped and listed in the report
+pub async fn fetch_perp_data(info_url: &str) -> Result<(PerpMapping, FetchReport), FetchError> {
+    let (meta, asset_ctxs) = Client::new()
+        .post(info_url)
+        .json(&serde_json::json!({"type": "metaAndAssetCtxs"}))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<PerpApiResponse>()
+        .await?;
+
+    // Contexts come in the same order as the universe
+    let mut perp_mapping: PerpMapping = HashMap::new();
+    let mut report = FetchReport::default();
+    for (asset, ctx) in meta.universe.iter().zip(asset_ctxs.iter()) {
+        if asset.is_delisted.unwrap_or(false) {
+            continue;
+        }
+        if let Some(perp_info) = report.push(parse_perp_info(asset, ctx)) {
+            perp_mapping.insert(asset.name.to_uppercase(), perp_info);
+        }
+    }
+
+    Ok((perp_mapping, report))
+}
+
+fn parse_perp_info(asset: &PerpAsset, ctx: &PerpAssetCtx) -> Result<PerpInfo, FetchError> {
+    Ok(PerpInfo {
+        name: asset.name.clone(),
+        funding: parse_field(&asset.name, "funding", &ctx.funding)?,
+        open_interest: parse_field(&asset.name, "openInterest", &ctx.open_interest)?,
+        mark_price: parse_field(&asset.name, "markPx", &ctx.mark_px)?,
+    })
+}
+
+type PerpApiResponse = (PerpMeta, Vec<PerpAssetCtx>);
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpMeta {
+    pub universe: Vec<PerpAsset>,
+}
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpAsset {
+    pub name: String,
+    pub sz_decimals: u32,
+    pub max_leverage: u32,
+    pub is_delisted: Option<bool>,
+}
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpAssetCtx {
+    pub funding: String,
+    pub open_interest: String,
+    pub mark_px: String,
+    pub prev_day_px: String,
+    pub day_ntl_vlm: String,
+}
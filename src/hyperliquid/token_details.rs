@@ -0,0 +1,46 @@
+// src/hyperliquid/token_details.rs
+
+use reqwest::Client;
+use serde::Deserialize;
+
+use crate::hyperliquid::error::FetchError;
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenDetails {
+    pub name: String,
+    pub max_supply: Option<String>,
+    pub total_supply: Option<String>,
+    pub circulating_supply: Option<String>,
+    pub mark_px: Option<String>,
+    pub deployer: Option<String>,
+    pub deploy_time: Option<String>,
+    pub seeded_usdc: Option<String>,
+}
+
+impl TokenDetails {
+    /// Max supply when set, total supply otherwise
+    pub fn fdv_supply(&self) -> Option<f64> {
+        let parse = |value: &Option<String>| value.as_deref()?.parse::<f64>().ok();
+        parse(&self.max_supply)
+            .filter(|supply| *supply > 0.0)
+            .or_else(|| parse(&self.total_supply))
+    }
+}
+
+pub async fn fetch_token_details(
+    info_url: &str,
+    token_id: &str,
+) -> Result<TokenDetails, FetchError> {
+    let details = Client::new()
+        .post(info_url)
+        .json(&serde_json::json!({"type": "tokenDetails", "tokenId": token_id}))
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<TokenDetails>()
+        .await?;
+
+    Ok(details)
+}
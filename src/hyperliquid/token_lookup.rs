@@ -0,0 +1,203 @@
+// src/hyperliquid/token_lookup.rs
+
+use crate::hyperliquid::fetch_price::{SpotUniverse, Token};
+
+const MAX_SUGGESTIONS: usize = 3;
+const MAX_FUZZY_DISTANCE: usize = 2;
+
+#[derive(Debug)]
+pub enum TokenLookup {
+    Found(Token),
+    Ambiguous(Vec<Token>),
+    NotFound { suggestions: Vec<String> },
+}
+
+/// Resolve a user query to a tradable spot token.
+/// Accepts a symbol, a full name, a token ID (`0x...`) or a pair (`@123`).
+pub fn lookup_token(universe: &SpotUniverse, query: &str) -> TokenLookup {
+    let query = query.trim();
+    let tradable: Vec<&Token> = universe
+        .tokens
+        .iter()
+        .filter(|token| {
+            universe
+                .pairs
+                .iter()
+                .any(|pair| pair.tokens.first() == Some(&(token.index as i32)))
+        })
+        .collect();
+
+    if let Some(pair_index) = query.strip_prefix('@') {
+        let base = pair_index.parse::<i32>().ok().and_then(|pair_index| {
+            universe
+                .pairs
+                .iter()
+                .find(|pair| pair.index == pair_index)
+                .and_then(|pair| pair.tokens.first())
+        });
+        return match base.and_then(|base| tradable.iter().find(|t| t.index as i32 == *base)) {
+            Some(token) => TokenLookup::Found((*token).clone()),
+            None => TokenLookup::NotFound {
+                suggestions: Vec::new(),
+            },
+        };
+    }
+
+    if query.starts_with("0x") {
+        return match tradable
+            .iter()
+            .find(|t| t.token_id.eq_ignore_ascii_case(query))
+        {
+            Some(token) => TokenLookup::Found((*token).clone()),
+            None => TokenLookup::NotFound {
+                suggestions: Vec::new(),
+            },
+        };
+    }
+
+    let by_symbol: Vec<&Token> = tradable
+        .iter()
+        .filter(|t| t.name.eq_ignore_ascii_case(query))
+        .cloned()
+        .collect();
+    let by_full_name: Vec<&Token> = tradable
+        .iter()
+        .filter(|t| {
+            t.full_name
+                .as_deref()
+                .is_some_and(|n| n.eq_ignore_ascii_case(query))
+        })
+        .cloned()
+        .collect();
+
+    let mut matches: Vec<Token> = Vec::new();
+    for token in by_symbol.into_iter().chain(by_full_name) {
+        if !matches.iter().any(|m| m.index == token.index) {
+            matches.push(token.clone());
+        }
+    }
+    match matches.len() {
+        0 => TokenLookup::NotFound {
+            suggestions: suggest(&tradable, query),
+        },
+        1 => TokenLookup::Found(matches.remove(0)),
+        _ => TokenLookup::Ambiguous(matches),
+    }
+}
+
+fn suggest(tokens: &[&Token], query: &str) -> Vec<String> {
+    let query = query.to_lowercase();
+    let mut scored: Vec<(usize, &str)> = tokens
+        .iter()
+        .filter_map(|token| {
+            let name_distance = levenshtein(&token.name.to_lowercase(), &query);
+            let full_name_distance = token
+                .full_name
+                .as_deref()
+                .map(|n| levenshtein(&n.to_lowercase(), &query))
+                .unwrap_or(usize::MAX);
+            let prefix = token.name.to_lowercase().starts_with(&query);
+            let distance = if prefix {
+                0
+            } else {
+                name_distance.min(full_name_distance)
+            };
+            (distance <= MAX_FUZZY_DISTANCE).then_some((distance, token.name.as_str()))
+        })
+        .collect();
+    scored.sort();
+    scored.dedup_by(|a, b| a.1 == b.1);
+    scored
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name.to_owned())
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hyperliquid::fetch_price::test_support::universe;
+
+    fn found(lookup: TokenLookup) -> usize {
+        match lookup {
+            TokenLookup::Found(token) => token.index,
+            other => panic!("expected a token, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lookup_by_symbol_id_and_pair() {
+        let universe = universe();
+        assert_eq!(found(lookup_token(&universe, " purre ")), 2);
+        assert_eq!(found(lookup_token(&universe, "Purr Cat")), 1);
+        assert_eq!(found(lookup_token(&universe, &format!("0x{:032X}", 2))), 2);
+        assert_eq!(found(lookup_token(&universe, "@12")), 3);
+    }
+
+    #[test]
+    fn lookup_skips_tokens_without_pair() {
+        let universe = universe();
+        assert!(matches!(
+            lookup_token(&universe, "LOST"),
+            TokenLookup::NotFound { .. }
+        ));
+        assert!(matches!(
+            lookup_token(&universe, "@99"),
+            TokenLookup::NotFound { .. }
+        ));
+    }
+
+    #[test]
+    fn lookup_symbol_and_full_name_collision_is_ambiguous() {
+        match lookup_token(&universe(), "PURR") {
+            TokenLookup::Ambiguous(tokens) => {
+                let indexes: Vec<usize> = tokens.iter().map(|t| t.index).collect();
+                assert_eq!(indexes, vec![1, 3]);
+            }
+            other => panic!("expected an ambiguous lookup, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn lookup_suggests_close_names() {
+        match lookup_token(&universe(), "HFNU") {
+            TokenLookup::NotFound { suggestions } => assert_eq!(suggestions, vec!["HFUN"]),
+            other => panic!("expected suggestions, got {:?}", other),
+        }
+        match lookup_token(&universe(), "PU") {
+            TokenLookup::NotFound { suggestions } => {
+                // Prefixes first, HFUN comes by its full name
+                assert_eq!(suggestions, vec!["PURR", "PURRE", "HFUN"])
+            }
+            other => panic!("expected suggestions, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn levenshtein_counts_edits() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("purr", "purr"), 0);
+        assert_eq!(levenshtein("purr", ""), 4);
+        assert_eq!(levenshtein("hfun", "hfnu"), 2);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+    }
+}
@@ -0,0 +1,146 @@
+// src/hyperliquid/user_state.rs
+
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::hyperliquid::error::FetchError;
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserFill {
+    pub coin: String,
+    pub px: String,
+    pub sz: String,
+    pub side: String,
+    pub time: i64,
+    pub dir: String,
+    pub closed_pnl: String,
+    pub hash: String,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+pub struct LedgerUpdate {
+    pub time: i64,
+    pub hash: String,
+    pub delta: Value, // shape depends on delta.type
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SpotBalance {
+    pub coin: String,
+    pub total: String,
+    pub entry_ntl: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SpotClearinghouseState {
+    balances: Vec<SpotBalance>,
+}
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerpPosition {
+    pub coin: String,
+    pub szi: String,
+    pub entry_px: Option<String>,
+    pub position_value: Option<String>,
+    pub unrealized_pnl: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssetPosition {
+    position: PerpPosition,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClearinghouseState {
+    asset_positions: Vec<AssetPosition>,
+}
+
+async fn post_info<T: for<'de> Deserialize<'de>>(
+    info_url: &str,
+    body: Value,
+) -> Result<T, FetchError> {
+    let response = Client::new()
+        .post(info_url)
+        .json(&body)
+        .send()
+        .await?
+        .error_for_status()?
+        .json::<T>()
+        .await?;
+    Ok(response)
+}
+
+pub async fn fetch_user_fills(info_url: &str, address: &str) -> Result<Vec<UserFill>, FetchError> {
+    post_info(
+        info_url,
+        serde_json::json!({"type": "userFills", "user": address}),
+    )
+    .await
+}
+
+pub async fn fetch_ledger_updates(
+    info_url: &str,
+    address: &str,
+    start_time: i64,
+) -> Result<Vec<LedgerUpdate>, FetchError> {
+    post_info(
+        info_url,
+        serde_json::json!({
+            "type": "userNonFundingLedgerUpdates",
+            "user": address,
+            "startTime": start_time
+        }),
+    )
+    .await
+}
+
+pub async fn fetch_spot_balances(
+    info_url: &str,
+    address: &str,
+) -> Result<Vec<SpotBalance>, FetchError> {
+    let state: SpotClearinghouseState = post_info(
+        info_url,
+        serde_json::json!({"type": "spotClearinghouseState", "user": address}),
+    )
+    .await?;
+    Ok(state.balances)
+}
+
+pub async fn fetch_perp_positions(
+    info_url: &str,
+    address: &str,
+) -> Result<Vec<PerpPosition>, FetchError> {
+    let state: ClearinghouseState = post_info(
+        info_url,
+        serde_json::json!({"type": "clearinghouseState", "user": address}),
+    )
+    .await?;
+    Ok(state
+        .asset_positions
+        .into_iter()
+        .map(|p| p.position)
+        .collect())
+}
+
+/// Best effort USD value of a ledger delta, depending on its type
+pub fn ledger_usd_value(delta: &Value) -> Option<f64> {
+    let field = match delta.get("type")?.as_str()? {
+        "spotTransfer" | "spotGenesis" => "usdcValue",
+        _ => "usdc",
+    };
+    delta
+        .get(field)?
+        .as_str()?
+        .parse::<f64>()
+        .ok()
+        .map(f64::abs)
+}
@@ -1,18 +1,7 @@
-use crate::*;
-use global_data::POOL;
 use sqlx::{Pool, Postgres};
-use std::sync::Arc;
 
-pub async fn init_pool(database_url: String) -> Result<(), Box<dyn std::error::Error>> {
+pub async fn init_pool(database_url: &str) -> Result<Pool<Postgres>, sqlx::Error> {
     info!("DB Pool instantiation");
 
-    let pool = Pool::<Postgres>::connect(&database_url)
-        .await
-        .expect("Failed to create pool");
-
-    // Set POOL only if it hasn't been initialized yet
-    POOL.set(Arc::new(pool))
-        .map_err(|_| "Global pool has already been set")?;
-
-    Ok(())
+    Pool::<Postgres>::connect(database_url).await
 }
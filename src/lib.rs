@@ -0,0 +1,20 @@
+pub mod bot;
+pub mod cli;
+pub mod clock;
+pub mod config;
+pub mod constants;
+pub mod context;
+pub mod db;
+pub mod error;
+pub mod handlers;
+pub mod health;
+pub mod hyperliquid;
+pub mod init;
+pub mod metrics;
+pub mod procedures;
+pub mod server;
+pub mod state;
+
+pub mod types;
+#[macro_use]
+extern crate log;
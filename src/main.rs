@@ -1,77 +1,114 @@
-mod bot;
-mod constants;
-mod db;
-mod global_data;
-mod handlers;
-mod hyperliquid;
-mod init;
-mod procedures;
-
-mod types;
 #[macro_use]
 extern crate log;
 
-use std::env;
-use std::sync::Arc;
+use std::process;
 
+use clap::Parser;
 use dotenv::dotenv;
-use global_data::{update_demand_data, update_token_data, BOT};
-use handlers::callback::callback_handler;
-use handlers::commands::commands_handler;
-use handlers::invites::handle_new_chat_members;
-
-use init::init_pool;
-
-use procedures::main::add_main_sequence;
 use teloxide::utils::command::BotCommands;
 use teloxide::{prelude::*, types::ChatKind};
 use tokio_cron_scheduler::JobScheduler;
-use types::commands::Command;
+use wagmi_alert::cli::{execute, Cli, CliCommand};
+use wagmi_alert::config::Config;
+use wagmi_alert::context::AppContext;
+use wagmi_alert::handlers::callback::callback_handler;
+use wagmi_alert::handlers::commands::commands_handler;
+use wagmi_alert::handlers::invites::handle_new_chat_members;
+use wagmi_alert::handlers::operator::operator_handler;
+use wagmi_alert::handlers::permissions::chat_member_handler;
+
+use wagmi_alert::procedures::error_summary::add_error_summary_sequence;
+use wagmi_alert::procedures::heartbeat::add_heartbeat_sequence;
+use wagmi_alert::procedures::main::add_main_sequence;
+use wagmi_alert::procedures::wallet_watch::add_wallet_watch_sequence;
+use wagmi_alert::server;
+use wagmi_alert::state::{update_perp_data, update_token_data};
+use wagmi_alert::types::commands::{Command, OperatorCommand};
 
 #[tokio::main]
 async fn main() {
     dotenv().ok();
 
     pretty_env_logger::init_timed();
+    let command = Cli::parse().command.unwrap_or(CliCommand::Run);
+
+    let config = match Config::load(command.needs_telegram()) {
+        Ok(config) => config,
+        Err(e) => {
+            error!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    match command {
+        CliCommand::Run => run(config).await,
+        command => {
+            if let Err(e) = execute(config, command).await {
+                error!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+}
 
+async fn run(config: Config) {
     info!("Bot instanciation");
 
-    let bot = Bot::from_env();
-
-    let db_url = env::var("DATABASE_URL").unwrap();
-    init_pool(db_url).await.expect("Could not init pool");
-    // init_bdd::check_and_init_db().await.map_err(|e| {
-    //     error!("{:#?}", e);
-    //     panic!()
-    // });
+    let ctx = AppContext::new(config)
+        .await
+        .expect("Could not build the app context");
 
     let commands = Command::bot_commands();
-    bot.set_my_commands(commands).await.unwrap(); // Clone bot when calling methods
-
-    update_token_data().await.expect("Cannot budate token data");
-    update_demand_data().await.expect("Cannod fetch demand ata");
-    update_demand_data().await.expect("Couldnt fetch map");
+    ctx.bot.set_my_commands(commands).await.unwrap(); // Clone bot when calling methods
+
+    update_token_data(&ctx)
+        .await
+        .expect("Cannot budate token data");
+    update_perp_data(&ctx)
+        .await
+        .expect("Cannot update perp data");
+    ctx.chat_demands
+        .reload(&ctx.pool)
+        .await
+        .expect("Cannod fetch demand ata");
 
     // Create the dependency map teloxide handler
     let handler = dptree::entry()
+        .branch(
+            Update::filter_message()
+                .filter(|msg: Message, ctx: AppContext| {
+                    msg.chat.is_private()
+                        && msg.from.as_ref().is_some_and(|u| ctx.is_operator(u.id))
+                })
+                .branch(
+                    teloxide::filter_command::<OperatorCommand, _>().endpoint(operator_handler),
+                ),
+        )
         .branch(
             Update::filter_message()
                 .filter(|msg: Message| matches!(msg.chat.kind, ChatKind::Public(_)))
                 .branch(teloxide::filter_command::<Command, _>().endpoint(commands_handler))
                 .branch(dptree::endpoint(message_handler)),
         )
-        .branch(Update::filter_callback_query().endpoint(callback_handler));
+        .branch(Update::filter_callback_query().endpoint(callback_handler))
+        .branch(Update::filter_chat_member().endpoint(chat_member_handler))
+        .branch(Update::filter_my_chat_member().endpoint(chat_member_handler));
 
-    BOT.set(Arc::new(bot.clone()))
-        .expect("Bot est déjà initialisé");
     let scheduler = JobScheduler::new().await.unwrap();
-    add_main_sequence(&scheduler).await;
+    add_main_sequence(&scheduler, ctx.clone()).await;
+    add_wallet_watch_sequence(&scheduler, ctx.clone()).await;
+    add_error_summary_sequence(&scheduler, ctx.clone()).await;
+    add_heartbeat_sequence(&scheduler, ctx.clone()).await;
     let scheduler_handle = tokio::spawn(async move {
         scheduler.start().await.unwrap();
     });
 
+    tokio::spawn(server::serve(ctx.config.http.addr.clone(), ctx.clone()));
+
     // Start the dispatcher
-    Dispatcher::builder(bot, handler)
+    ctx.status.set_dispatcher_running(true);
+    Dispatcher::builder(ctx.bot.clone(), handler)
+        .dependencies(dptree::deps![ctx.clone()])
         .default_handler(|upd| async move {
             warn!("Unhandled update: {:?}", upd);
         })
@@ -81,12 +118,13 @@ async fn main() {
         .build()
         .dispatch()
         .await;
+    ctx.status.set_dispatcher_running(false);
 
     // Wait for the scheduler task (if necessary)
     scheduler_handle.await.unwrap();
 }
 
-pub async fn message_handler(bot: Bot, msg: Message) -> anyhow::Result<()> {
-    handle_new_chat_members(bot, &msg).await?;
+pub async fn message_handler(ctx: AppContext, msg: Message) -> anyhow::Result<()> {
+    handle_new_chat_members(&ctx, &msg).await?;
     Ok(())
 }
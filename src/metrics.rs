@@ -0,0 +1,86 @@
+// src/metrics.rs
+
+use lazy_static::lazy_static;
+use prometheus::{
+    register_histogram_vec, register_int_counter_vec, register_int_gauge, register_int_gauge_vec,
+    Encoder, HistogramTimer, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, TextEncoder,
+};
+
+lazy_static! {
+    pub static ref FETCH_DURATION: HistogramVec = register_histogram_vec!(
+        "wagmi_fetch_duration_seconds",
+        "Latency of the Hyperliquid info fetches",
+        &["source"]
+    )
+    .unwrap();
+    pub static ref FETCH_FAILURES: IntCounterVec = register_int_counter_vec!(
+        "wagmi_fetch_failures_total",
+        "Hyperliquid fetches that failed entirely",
+        &["source"]
+    )
+    .unwrap();
+    pub static ref FETCH_SKIPPED_ITEMS: IntGaugeVec = register_int_gauge_vec!(
+        "wagmi_fetch_skipped_items",
+        "Malformed items skipped by the last fetch",
+        &["source"]
+    )
+    .unwrap();
+    pub static ref TOKENS_TRACKED: IntGauge =
+        register_int_gauge!("wagmi_tokens_tracked", "Spot tokens in the token map").unwrap();
+    pub static ref PERPS_TRACKED: IntGauge =
+        register_int_gauge!("wagmi_perps_tracked", "Perps in the perp map").unwrap();
+    pub static ref DEMANDS: IntGaugeVec =
+        register_int_gauge_vec!("wagmi_demands", "Stored demands", &["type_of", "interval"])
+            .unwrap();
+    pub static ref ALERTS_SENT: IntCounterVec = register_int_counter_vec!(
+        "wagmi_alerts_sent_total",
+        "Alert messages delivered",
+        &["kind"]
+    )
+    .unwrap();
+    pub static ref ALERTS_FAILED: IntCounterVec = register_int_counter_vec!(
+        "wagmi_alerts_failed_total",
+        "Alert messages Telegram refused",
+        &["kind"]
+    )
+    .unwrap();
+    pub static ref TELEGRAM_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "wagmi_telegram_errors_total",
+        "Failed Telegram API calls",
+        &["method"]
+    )
+    .unwrap();
+    pub static ref SCHEDULER_RUN_DURATION: HistogramVec = register_histogram_vec!(
+        "wagmi_scheduler_run_duration_seconds",
+        "Duration of the scheduled jobs",
+        &["job"]
+    )
+    .unwrap();
+    pub static ref DB_QUERY_DURATION: HistogramVec = register_histogram_vec!(
+        "wagmi_db_query_duration_seconds",
+        "Latency of the database queries",
+        &["query"]
+    )
+    .unwrap();
+}
+
+/// Observes the query duration when dropped
+pub fn db_timer(query: &str) -> HistogramTimer {
+    DB_QUERY_DURATION.with_label_values(&[query]).start_timer()
+}
+
+pub fn record_alert(kind: &str, delivered: bool) {
+    if delivered {
+        ALERTS_SENT.with_label_values(&[kind]).inc();
+    } else {
+        ALERTS_FAILED.with_label_values(&[kind]).inc();
+    }
+}
+
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&prometheus::gather(), &mut buffer) {
+        error!("Could not encode metrics: {}", e);
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
@@ -1,6 +1,6 @@
 pub mod bot;
 pub mod constants;
-pub mod global_data;
+pub mod state;
 pub mod hyperliquid;
 pub mod init;
 pub mod standards;
@@ -0,0 +1,18 @@
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+use crate::{
+    bot::send_error_summary, constants::schedules::CRON_ERROR_SUMMARY, context::AppContext,
+};
+
+pub async fn add_error_summary_sequence(scheduler: &JobScheduler, ctx: AppContext) {
+    scheduler
+        .add(
+            Job::new_async(CRON_ERROR_SUMMARY, move |_uuid, _l| {
+                let ctx = ctx.clone();
+                Box::pin(async move { send_error_summary(&ctx).await })
+            })
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+}
@@ -1,71 +1,73 @@
 use teloxide::types::{ChatId, MessageId, ThreadId};
 
 use crate::{
-    bot::{send_error, send_error_to_moderator, send_message},
+    bot::{send_alert, send_error, send_error_to_moderator},
+    context::AppContext,
     db::services::{
         demands::{batch_fetch_last_demands_by_time, Demand},
         tokens::{fetch_latest_tokens_at, TokensAt},
     },
-    global_data::{TokenMapping, REFERRAL_LINK},
     hyperliquid::fetch_price::TokenInfo,
+    state::TokenMapping,
 };
 // use anyhow::anyhow;
 
 const DEMAND_ERR_HEADER: &str = "Error satisfying demand for :";
 
-pub async fn execute_demands(tokens_at: TokensAt) {
-    match batch_fetch_last_demands_by_time(tokens_at.times.clone()).await {
+pub async fn execute_demands(ctx: &AppContext, tokens_at: TokensAt) {
+    match batch_fetch_last_demands_by_time(&ctx.pool, tokens_at.times.clone()).await {
         Err(e) => {
             error!("ERROOR for batch_fetch_last_demands_by_time");
-            return send_error_to_moderator(format!(
-                "Error durin getting map demand {}",
-                e.to_string()
-            ));
+            send_error_to_moderator(ctx, format!("Error durin getting map demand {}", e));
         }
         Ok(demand_map) => {
             debug!("Satisfying time");
             let mut err_stack = DEMAND_ERR_HEADER.to_owned();
             for time in tokens_at.times {
                 if let Some(demands) = demand_map.get(&time) {
-                    if let Err(e) =
-                        satisfy_regular_demands_at(demands.to_owned(), &time, &tokens_at.tokens)
-                            .await
+                    if let Err(e) = satisfy_regular_demands_at(
+                        ctx,
+                        demands.to_owned(),
+                        &time,
+                        &tokens_at.tokens,
+                    )
+                    .await
                     {
                         err_stack.push_str(&format!("{:?}{:?}", time, e));
                     }
                 }
             }
             if err_stack != DEMAND_ERR_HEADER {
-                send_error_to_moderator(err_stack);
+                send_error_to_moderator(ctx, err_stack);
             }
         }
     }
 }
 
 pub async fn satisfy_regular_demands_at(
+    ctx: &AppContext,
     demands: Vec<Demand>,
     time: &str,
     tokens_now: &TokenMapping,
 ) -> anyhow::Result<()> {
     debug!("Satisfying demand {:#?}", demands);
-    if demands.len() > 0 {
-        if let Some(tokens_at) = fetch_latest_tokens_at(time).await? {
+    if !demands.is_empty() {
+        if let Some(tokens_at) = fetch_latest_tokens_at(&ctx.pool, time).await? {
             debug!("fetched last token at {time}");
             for demand in demands {
-                let token = demand
-                    .token
-                    .clone()
-                    // .expect("No token in demand")
-                    .to_uppercase();
+                let token = demand.token.clone();
 
                 if let Some(new_token) = tokens_now.get(&token) {
-                    let latest = tokens_at.tokens.get(&token).unwrap();
-                    process_tokens(demand, new_token, latest)?;
+                    match tokens_at.tokens.get(&token) {
+                        Some(latest) => process_tokens(ctx, demand, new_token, latest)?,
+                        None => debug!("No previous price for {token} at {time}"),
+                    }
                 } else {
                     debug!("Nothing for {token}");
                     send_error(
+                        ctx,
                         ChatId(demand.chat_id),
-                        "No pair for {token} ",
+                        &format!("No pair for token \\#{token} anymore"),
                         demand.thread_id.map(|id| ThreadId(MessageId(id))),
                     );
                 }
@@ -78,38 +80,62 @@ pub async fn satisfy_regular_demands_at(
     }
     Ok(())
 }
-fn process_tokens(demand: Demand, new: &TokenInfo, previous: &TokenInfo) -> anyhow::Result<()> {
-    let diff_wanted = demand.percentage;
-    debug!("Diff wanted {diff_wanted}, for demand {}", demand.chat_id);
-    let diff = (new.price - previous.price) / previous.price * 100_f64;
-    if diff.abs() < 0.01 || diff.abs() < diff_wanted as f64 {
-        // debug!("price token info{:?} no dif", new.full_name);
-        Ok(())
-    } else {
+fn process_tokens(
+    ctx: &AppContext,
+    demand: Demand,
+    new: &TokenInfo,
+    previous: &TokenInfo,
+) -> anyhow::Result<()> {
+    debug!(
+        "Diff wanted {}, for demand {}",
+        demand.percentage, demand.chat_id
+    );
+    if let Some(diff) = demand_diff(&demand, new, previous) {
         let msg = format_dif_message(
-            new.price,
+            ctx.referral_link(),
+            &new.format_price(),
             diff,
             new.pair_number.unwrap_or_default(),
             &demand.interval,
             &new.name,
-        );
+        ) + "\n"
+            + &new.format_caps();
         debug!("Sending for demand{} dif", msg);
         let chat_id = ChatId(demand.chat_id);
-        send_message(
+        send_alert(
+            ctx,
             chat_id,
             &msg,
             demand.thread_id.map(|id| ThreadId(MessageId(id))),
+            &demand.type_of,
         );
-        Ok(())
+    }
+    Ok(())
+}
+
+/// Move in percent since `previous` when it reaches the demand, shared with the replay
+pub fn demand_diff(demand: &Demand, new: &TokenInfo, previous: &TokenInfo) -> Option<f64> {
+    let diff = (new.price - previous.price) / previous.price * 100_f64;
+    if diff.abs() < 0.01 || diff.abs() < demand.percentage as f64 {
+        None
+    } else {
+        Some(diff)
     }
 }
 
 //
-fn format_dif_message(price: f64, diff: f64, pair_no: u16, time: &str, name: &str) -> String {
+fn format_dif_message(
+    referral_link: &str,
+    price: &str,
+    diff: f64,
+    pair_no: u16,
+    time: &str,
+    name: &str,
+) -> String {
     let movement = if diff <= 0.0 { "dropped" } else { "risen" };
     let diff = format!("{:.2}", diff);
     format!(
-        "__*📈 WAGMI Alert*__:\n[{}]({}{}) has {} by {}% in the last {} : {}$",
-        name, REFERRAL_LINK, pair_no, movement, diff, time, price
+        "__*📈 WAGMI Alert*__:\n[{}]({}{}) has {} by {}% in the last {} : {}",
+        name, referral_link, pair_no, movement, diff, time, price
     )
 }
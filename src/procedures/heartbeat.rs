@@ -0,0 +1,17 @@
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+use crate::{constants::schedules::CRON_HEARTBEAT, context::AppContext};
+
+/// Proves the scheduler still runs jobs, checked by /healthz
+pub async fn add_heartbeat_sequence(scheduler: &JobScheduler, ctx: AppContext) {
+    scheduler
+        .add(
+            Job::new_async(CRON_HEARTBEAT, move |_uuid, _l| {
+                let ctx = ctx.clone();
+                Box::pin(async move { ctx.status.beat(ctx.clock.now().timestamp()) })
+            })
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use teloxide::types::{ChatId, MessageId, ThreadId};
+
+use crate::{
+    bot::{send_alert, send_error_to_moderator, utils::escape_user_text},
+    constants::liquidity::DEPTH_UNIT_USD,
+    context::AppContext,
+    db::services::demands::{get_demands_by_type, Demand},
+    hyperliquid::{
+        fetch_price::TokenInfo,
+        l2_book::{compute_liquidity, fetch_l2_book, LiquidityMetrics},
+    },
+    state::TokenMapping,
+    types::commands::{DEPTH, SPREAD},
+};
+
+const LIQUIDITY_HEADER: &str = "__*💧 WAGMI Liquidity Alert*__:\n";
+const LIQUIDITY_ERR_HEADER: &str = "Error satisfying liquidity demand for :";
+
+pub async fn execute_liquidity_demands(ctx: &AppContext, tokens: &TokenMapping) {
+    let mut err_stack = LIQUIDITY_ERR_HEADER.to_owned();
+
+    let demands = match get_demands_by_type(&ctx.pool, SPREAD).await {
+        Ok(mut demands) => match get_demands_by_type(&ctx.pool, DEPTH).await {
+            Ok(depth_demands) => {
+                demands.extend(depth_demands);
+                demands
+            }
+            Err(e) => return send_error_to_moderator(ctx, format!("{err_stack}{:?}", e)),
+        },
+        Err(e) => return send_error_to_moderator(ctx, format!("{err_stack}{:?}", e)),
+    };
+    if demands.is_empty() {
+        return;
+    }
+
+    // One book per token whatever the number of demands on it
+    let depth_range_percent = ctx.config.thresholds.depth_range_percent;
+    let mut metrics: HashMap<String, LiquidityMetrics> = HashMap::new();
+    for demand in demands.iter() {
+        if metrics.contains_key(&demand.token) {
+            continue;
+        }
+        let token = match tokens.get(&demand.token) {
+            Some(token) if !token.coin.is_empty() => token,
+            _ => continue,
+        };
+        // Books are in quote units
+        let quote_usd = token
+            .pair
+            .as_ref()
+            .filter(|pair| pair.price_in_quote > 0.0)
+            .map_or(1.0, |pair| token.price / pair.price_in_quote);
+        match fetch_l2_book(&ctx.config.hyperliquid.info_url, &token.coin).await {
+            Ok(book) => match compute_liquidity(&book, depth_range_percent) {
+                Some(m) => {
+                    metrics.insert(demand.token.clone(), m.in_usd(quote_usd));
+                }
+                None => debug!("One sided book for {}", demand.token),
+            },
+            Err(e) => err_stack.push_str(&format!("{} {:?}\n", demand.token, e)),
+        }
+    }
+
+    for demand in demands {
+        let (breached, metric) = match metrics.get(&demand.token) {
+            Some(metric) => (is_breached(&demand, metric), metric),
+            None => continue,
+        };
+        if ctx
+            .market
+            .update_liquidity_breach(demand.get_composite_id(), breached)
+            .await
+        {
+            let token = match tokens.get(&demand.token) {
+                Some(token) => token,
+                None => continue,
+            };
+            let msg = format_liquidity_message(ctx, &demand, metric, token);
+            send_alert(
+                ctx,
+                ChatId(demand.chat_id),
+                &msg,
+                demand.thread_id.map(|id| ThreadId(MessageId(id))),
+                &demand.type_of,
+            );
+        }
+    }
+
+    if err_stack != LIQUIDITY_ERR_HEADER {
+        send_error_to_moderator(ctx, err_stack);
+    }
+}
+
+pub fn is_breached(demand: &Demand, metrics: &LiquidityMetrics) -> bool {
+    match demand.type_of.as_str() {
+        SPREAD => metrics.spread_bps > demand.percentage as f64,
+        DEPTH => metrics.bid_depth < demand.percentage as f64 * DEPTH_UNIT_USD,
+        _ => false,
+    }
+}
+
+fn format_liquidity_message(
+    ctx: &AppContext,
+    demand: &Demand,
+    metrics: &LiquidityMetrics,
+    token: &TokenInfo,
+) -> String {
+    let condition = match demand.type_of.as_str() {
+        SPREAD => format!("spread widened beyond {} bps", demand.percentage),
+        _ => format!("bid depth dropped below {}k$", demand.percentage),
+    };
+    let depth_range_percent = ctx.config.thresholds.depth_range_percent;
+    format!(
+        "{LIQUIDITY_HEADER}[{}]({}{}) {}\nMid: {}$\nSpread: {:.1} bps\nBid depth ±{}%: {:.0}$\nAsk depth ±{}%: {:.0}$\n{}",
+        escape_user_text(&token.name),
+        ctx.referral_link(),
+        token.pair_number.unwrap_or_default(),
+        condition,
+        metrics.mid,
+        metrics.spread_bps,
+        depth_range_percent,
+        metrics.bid_depth,
+        depth_range_percent,
+        metrics.ask_depth,
+        token.format_caps()
+    )
+}
@@ -0,0 +1,369 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    bot::{broadcast_message, send_error_to_moderator, utils::escape_user_text},
+    context::AppContext,
+    db::services::{
+        demands::get_all_listing_chat_id,
+        universe::{
+            fetch_known_pairs, fetch_known_tokens, save_known_universe, KnownPair, KnownToken,
+        },
+    },
+    hyperliquid::{
+        fetch_price::{pair_number, SpotUniverse},
+        token_details::fetch_token_details,
+    },
+    types::commands::LISTING,
+};
+
+const LISTING_HEADER: &str = "__*🆕 WAGMI New Listing:*__\n\n";
+const LISTING_ERROR_HEADER: &str = "LISTING_ERROR\n";
+const SEPARATOR: &str = "------------------------\n";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct UniverseChanges {
+    pub new_tokens: Vec<KnownToken>,
+    pub new_pairs: Vec<KnownPair>,
+    pub delisted_tokens: Vec<KnownToken>,
+    pub delisted_pairs: Vec<KnownPair>,
+}
+
+impl UniverseChanges {
+    pub fn is_empty(&self) -> bool {
+        self.new_tokens.is_empty()
+            && self.new_pairs.is_empty()
+            && self.delisted_tokens.is_empty()
+            && self.delisted_pairs.is_empty()
+    }
+}
+
+pub async fn check_and_send_listings(ctx: &AppContext) {
+    let universe = ctx.market.universe().await;
+    if universe.tokens.is_empty() {
+        // An empty fetch would look like a mass delisting
+        return;
+    }
+    let (mut tokens, mut pairs) = known_from_universe(&universe);
+
+    let (previous_tokens, previous_pairs) =
+        match tokio::try_join!(fetch_known_tokens(&ctx.pool), fetch_known_pairs(&ctx.pool)) {
+            Ok(known) => known,
+            Err(e) => {
+                return send_error_to_moderator(
+                    ctx,
+                    format!(
+                        "{LISTING_ERROR_HEADER}Error while fetching known universe: {:?}",
+                        e
+                    ),
+                )
+            }
+        };
+
+    if !universe.complete {
+        // Skipped items would look delisted, then listed again at the next complete fetch
+        keep_missing(&previous_tokens, &mut tokens, |t| t.token_index);
+        keep_missing(&previous_pairs, &mut pairs, |p| p.pair_index);
+    }
+
+    // Saving before broadcasting so a failed broadcast never repeats the alert
+    if let Err(e) = save_known_universe(&ctx.pool, &tokens, &pairs).await {
+        return send_error_to_moderator(
+            ctx,
+            format!("{LISTING_ERROR_HEADER}Error while saving universe: {:?}", e),
+        );
+    }
+
+    if previous_tokens.is_empty() && previous_pairs.is_empty() {
+        info!("Known universe seeded with {} tokens", tokens.len());
+        return;
+    }
+
+    let changes = diff_universe(&previous_tokens, &previous_pairs, &tokens, &pairs);
+    if changes.is_empty() {
+        return;
+    }
+    info!("Universe changes {:?}", changes);
+
+    let message = generate_listing_alert(ctx, &changes, &universe, &previous_tokens).await;
+    match get_all_listing_chat_id(&ctx.pool).await {
+        Err(e) => send_error_to_moderator(
+            ctx,
+            format!(
+                "{LISTING_ERROR_HEADER}Error while getting all chat_ids: {:?}",
+                e
+            ),
+        ),
+        Ok(all_chat_ids) => {
+            if let Err(e) = broadcast_message(ctx, all_chat_ids, message, LISTING).await {
+                send_error_to_moderator(
+                    ctx,
+                    format!("{LISTING_ERROR_HEADER}Error while broadcasting: {:?}", e),
+                )
+            } else {
+                info!("Listing message broadcasted")
+            }
+        }
+    }
+}
+
+pub fn known_from_universe(universe: &SpotUniverse) -> (Vec<KnownToken>, Vec<KnownPair>) {
+    let tokens = universe
+        .tokens
+        .iter()
+        .map(|token| KnownToken {
+            token_index: token.index as i32,
+            name: token.name.clone(),
+            token_id: token.token_id.clone(),
+            is_canonical: token.is_canonical,
+        })
+        .collect();
+    let pairs = universe
+        .pairs
+        .iter()
+        .filter(|pair| pair.tokens.len() == 2)
+        .map(|pair| KnownPair {
+            pair_index: pair.index,
+            name: pair.name.clone(),
+            base_index: pair.tokens[0],
+            quote_index: pair.tokens[1],
+        })
+        .collect();
+    (tokens, pairs)
+}
+
+/// Adds back the previous entries the current fetch doesn't have
+fn keep_missing<T: Clone>(previous: &[T], current: &mut Vec<T>, index: impl Fn(&T) -> i32) {
+    let indexes: HashSet<i32> = current.iter().map(&index).collect();
+    current.extend(
+        previous
+            .iter()
+            .filter(|entry| !indexes.contains(&index(entry)))
+            .cloned(),
+    );
+}
+
+pub fn diff_universe(
+    previous_tokens: &[KnownToken],
+    previous_pairs: &[KnownPair],
+    tokens: &[KnownToken],
+    pairs: &[KnownPair],
+) -> UniverseChanges {
+    let known_token_indexes: HashSet<i32> = previous_tokens.iter().map(|t| t.token_index).collect();
+    let known_pair_indexes: HashSet<i32> = previous_pairs.iter().map(|p| p.pair_index).collect();
+    let token_indexes: HashSet<i32> = tokens.iter().map(|t| t.token_index).collect();
+    let pair_indexes: HashSet<i32> = pairs.iter().map(|p| p.pair_index).collect();
+
+    let new_tokens: Vec<KnownToken> = tokens
+        .iter()
+        .filter(|t| !known_token_indexes.contains(&t.token_index))
+        .cloned()
+        .collect();
+    let new_pairs = pairs
+        .iter()
+        .filter(|p| !known_pair_indexes.contains(&p.pair_index))
+        .cloned()
+        .collect();
+    let delisted_tokens = previous_tokens
+        .iter()
+        .filter(|t| !token_indexes.contains(&t.token_index))
+        .cloned()
+        .collect();
+    let delisted_pairs = previous_pairs
+        .iter()
+        .filter(|p| !pair_indexes.contains(&p.pair_index))
+        .cloned()
+        .collect();
+
+    UniverseChanges {
+        new_tokens,
+        new_pairs,
+        delisted_tokens,
+        delisted_pairs,
+    }
+}
+
+async fn generate_listing_alert(
+    ctx: &AppContext,
+    changes: &UniverseChanges,
+    universe: &SpotUniverse,
+    previous_tokens: &[KnownToken],
+) -> String {
+    // Delisted tokens are only in the previous universe
+    let names: HashMap<i32, &str> = previous_tokens
+        .iter()
+        .map(|t| (t.token_index, t.name.as_str()))
+        .chain(
+            universe
+                .tokens
+                .iter()
+                .map(|t| (t.index as i32, t.name.as_str())),
+        )
+        .collect();
+    let pair_label = |pair: &KnownPair| {
+        format!(
+            "{}/{}",
+            names.get(&pair.base_index).unwrap_or(&"?"),
+            names.get(&pair.quote_index).unwrap_or(&"?")
+        )
+    };
+    let tokens_now = ctx.market.tokens().await;
+    let mut alert_message = LISTING_HEADER.to_string();
+
+    for token in &changes.new_tokens {
+        let full_name = universe
+            .tokens
+            .iter()
+            .find(|t| t.index as i32 == token.token_index)
+            .and_then(|t| t.full_name.clone())
+            .map(|n| format!(" \\({}\\)", escape_user_text(&n)))
+            .unwrap_or_default();
+        let name = escape_user_text(&token.name);
+
+        match changes
+            .new_pairs
+            .iter()
+            .find(|p| p.base_index == token.token_index)
+        {
+            Some(pair) => alert_message.push_str(&format!(
+                "__[{}]({}{})__{}: New token listed\n",
+                name,
+                ctx.referral_link(),
+                pair_number(pair.pair_index as u32).unwrap_or_default(),
+                full_name
+            )),
+            None => alert_message.push_str(&format!(
+                "*{}*{}: New token deployed, no pair yet\n",
+                name, full_name
+            )),
+        }
+
+        match fetch_token_details(&ctx.config.hyperliquid.info_url, &token.token_id).await {
+            Ok(details) => {
+                if let Some(deployer) = details.deployer {
+                    alert_message.push_str(&format!("Deployer: `{}`\n", deployer));
+                }
+                if let Some(deploy_time) = details.deploy_time {
+                    alert_message.push_str(&format!("Deployed at: {}\n", deploy_time));
+                }
+            }
+            Err(e) => warn!("No token details for {}: {}", token.name, e),
+        }
+
+        if let Some(price) = changes
+            .new_pairs
+            .iter()
+            .find(|p| p.base_index == token.token_index)
+            .and_then(|p| universe.mark_prices.get(&p.name))
+        {
+            alert_message.push_str(&format!("Initial price: {}$\n", price));
+        }
+        if let Some(token_info) = tokens_now.get(&token.token_index.to_string()) {
+            alert_message.push_str(&format!("{}\n", token_info.format_caps()));
+        }
+        alert_message.push_str(SEPARATOR);
+    }
+
+    let new_token_indexes: HashSet<i32> =
+        changes.new_tokens.iter().map(|t| t.token_index).collect();
+    for pair in changes
+        .new_pairs
+        .iter()
+        .filter(|p| !new_token_indexes.contains(&p.base_index))
+    {
+        alert_message.push_str(&format!(
+            "__[{}]({}{})__: New pair listed\n",
+            escape_user_text(&pair_label(pair)),
+            ctx.referral_link(),
+            pair_number(pair.pair_index as u32).unwrap_or_default()
+        ));
+        if let Some(price) = universe.mark_prices.get(&pair.name) {
+            alert_message.push_str(&format!("Initial price: {}$\n", price));
+        }
+        alert_message.push_str(SEPARATOR);
+    }
+
+    for token in &changes.delisted_tokens {
+        alert_message.push_str(&format!(
+            "*{}*: Token delisted\n{}",
+            escape_user_text(&token.name),
+            SEPARATOR
+        ));
+    }
+
+    let delisted_token_indexes: HashSet<i32> = changes
+        .delisted_tokens
+        .iter()
+        .map(|t| t.token_index)
+        .collect();
+    for pair in changes
+        .delisted_pairs
+        .iter()
+        .filter(|p| !delisted_token_indexes.contains(&p.base_index))
+    {
+        alert_message.push_str(&format!(
+            "*{}*: Pair delisted\n{}",
+            escape_user_text(&pair_label(pair)),
+            SEPARATOR
+        ));
+    }
+
+    alert_message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(index: i32, name: &str) -> KnownToken {
+        KnownToken {
+            token_index: index,
+            name: name.to_owned(),
+            token_id: format!("0x{:032x}", index),
+            is_canonical: false,
+        }
+    }
+
+    fn pair(index: i32, base: i32, quote: i32) -> KnownPair {
+        KnownPair {
+            pair_index: index,
+            name: format!("@{}", index),
+            base_index: base,
+            quote_index: quote,
+        }
+    }
+
+    #[test]
+    fn diff_universe_finds_listings() {
+        let previous_tokens = [token(0, "USDC"), token(1, "PURR")];
+        let previous_pairs = [pair(0, 1, 0)];
+        let tokens = [token(0, "USDC"), token(1, "PURR"), token(2, "HFUN")];
+        let pairs = [pair(0, 1, 0), pair(1, 2, 0), pair(2, 2, 1)];
+
+        let changes = diff_universe(&previous_tokens, &previous_pairs, &tokens, &pairs);
+        assert_eq!(changes.new_tokens, vec![token(2, "HFUN")]);
+        assert_eq!(changes.new_pairs, vec![pair(1, 2, 0), pair(2, 2, 1)]);
+        assert!(changes.delisted_tokens.is_empty());
+        assert!(changes.delisted_pairs.is_empty());
+    }
+
+    #[test]
+    fn diff_universe_finds_delistings() {
+        let previous_tokens = [token(0, "USDC"), token(1, "PURR"), token(2, "HFUN")];
+        let previous_pairs = [pair(0, 1, 0), pair(1, 2, 0), pair(2, 1, 2)];
+        let tokens = [token(0, "USDC"), token(1, "PURR")];
+        let pairs = [pair(0, 1, 0)];
+
+        let changes = diff_universe(&previous_tokens, &previous_pairs, &tokens, &pairs);
+        assert!(changes.new_tokens.is_empty());
+        assert!(changes.new_pairs.is_empty());
+        assert_eq!(changes.delisted_tokens, vec![token(2, "HFUN")]);
+        assert_eq!(changes.delisted_pairs, vec![pair(1, 2, 0), pair(2, 1, 2)]);
+    }
+
+    #[test]
+    fn diff_universe_same_universe_is_empty() {
+        let tokens = [token(0, "USDC"), token(1, "PURR")];
+        let pairs = [pair(0, 1, 0)];
+        assert!(diff_universe(&tokens, &pairs, &tokens, &pairs).is_empty());
+    }
+}
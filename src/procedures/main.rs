@@ -1,25 +1,40 @@
 use crate::bot::send_error_to_moderator;
-use crate::constants::schedules::INTERVALS;
+use crate::config::IntervalsConfig;
+use crate::constants::schedules::{CRON_MATCH_TOLERANCE_SECS, INTERVAL_15MIN};
+use crate::context::AppContext;
 // use crate::db::diesel::tokens_at::timestamp_in_min;
+use crate::db::services::demands::count_demands_by_type_and_interval;
 use crate::db::services::tokens::TokensAt;
-use crate::global_data::{get_last_token_map, update_token_data};
+use crate::hyperliquid::error::FetchReport;
+use crate::metrics::{DEMANDS, SCHEDULER_RUN_DURATION};
 use crate::procedures::fill_demands::execute_demands;
+use crate::procedures::liquidity_alerts::execute_liquidity_demands;
+use crate::procedures::listing_alert::check_and_send_listings;
+use crate::procedures::market_cap_alerts::execute_market_cap_demands;
+use crate::procedures::perp_alerts::execute_perp_demands;
 use crate::procedures::pump_alert::check_and_send_pump;
+use crate::state::{update_perp_data, update_token_data, RunStatus};
 use chrono::prelude::*;
 use cron_clock::Schedule;
-use tokio::time::{sleep, Duration};
+use tokio::time::{sleep, Duration, Instant};
 
 use std::str::FromStr;
 use tokio_cron_scheduler::{Job, JobScheduler};
 
-pub async fn add_main_sequence(scheduler: &JobScheduler) {
-    let (_, schedule) = INTERVALS.first().expect("Couldn gain cron expression");
+pub async fn add_main_sequence(scheduler: &JobScheduler, ctx: AppContext) {
+    let schedule = ctx
+        .config
+        .intervals
+        .cron(INTERVAL_15MIN)
+        .expect("Couldn gain cron expression")
+        .to_owned();
 
     // Schedule the job
     scheduler
         .add(
-            Job::new_async(schedule, move |_uuid, _l| {
-                Box::pin(async move { execute_sequence().await })
+            Job::new_async(schedule.as_str(), move |_uuid, _l| {
+                let ctx = ctx.clone();
+                Box::pin(async move { execute_sequence(&ctx).await })
             })
             .unwrap(),
         )
@@ -27,48 +42,137 @@ pub async fn add_main_sequence(scheduler: &JobScheduler) {
         .unwrap();
 }
 
-async fn execute_sequence() {
-    let now = Utc::now();
+/// The intervals and the snapshot minute come from the context clock
+pub async fn execute_sequence(ctx: &AppContext) {
+    if ctx.status.is_paused() {
+        info!("Scheduler paused, skipping sequence");
+        return;
+    }
+    let _timer = SCHEDULER_RUN_DURATION
+        .with_label_values(&["main"])
+        .start_timer();
+    let run_start = Instant::now();
+    let now = ctx.clock.now();
     let timestamp_in_min = (now.timestamp() / 60) as i32;
 
-    let mut times: Vec<String> = Vec::new();
+    let times = matching_intervals(&ctx.config.intervals, now);
 
-    for (name, cron_str) in INTERVALS {
-        if is_time_matching(cron_str, now.clone()) {
-            times.push(name.to_string());
+    info!("Fetching Datas for :Handeling {:?}", times);
+    match update_token_data(ctx).await {
+        Ok(report) => report_skipped_items(ctx, "spot", &report),
+        Err(e) => {
+            send_error_to_moderator(ctx, format!("Error during fetch {}", e));
+            record_last_run(ctx, now, run_start, times, Some(e.to_string())).await;
+            return;
         }
     }
-
-    info!("Fetching Datas for :Handeling {:?}", times);
-    if let Err(e) = update_token_data().await {
-        send_error_to_moderator(format!("Error during fetch {:?}", e));
-        return;
+    match update_perp_data(ctx).await {
+        Ok(report) => report_skipped_items(ctx, "perp", &report),
+        Err(e) => send_error_to_moderator(ctx, format!("Error during perp fetch {}", e)),
     }
 
     info!("Executing check pump");
-    check_and_send_pump().await;
+    check_and_send_pump(ctx).await;
+
+    info!("Executing check listings");
+    check_and_send_listings(ctx).await;
 
-    let tokens = get_last_token_map().await;
+    let tokens = ctx.market.tokens().await;
+    let perps = ctx.market.perps().await;
 
     let tokens_at = TokensAt {
         tokens,
-        times,
+        times: times.clone(),
         timestamp_in_min,
+        perps,
     };
 
     info!("Executing regular demand");
-    execute_demands(tokens_at.clone()).await;
+    execute_demands(ctx, tokens_at.clone()).await;
+
+    // Perp and market cap alerts compare with the latest stored snapshot,
+    // so they must run before `tokens_at` is stored
+    info!("Executing perp demand");
+    execute_perp_demands(ctx, &tokens_at).await;
+
+    info!("Executing market cap demand");
+    execute_market_cap_demands(ctx, &tokens_at).await;
+
+    info!("Executing liquidity demand");
+    execute_liquidity_demands(ctx, &tokens_at.tokens).await;
 
     info!("Updating database");
-    if let Err(_) = tokens_at.insert().await {
+    let mut run_error = None;
+    if tokens_at.insert(&ctx.pool).await.is_err() {
         sleep(Duration::from_secs(1)).await;
-        if let Err(e) = tokens_at.insert().await {
-            send_error_to_moderator(format!("Error pushing in database 2 times{:?}", e));
+        if let Err(e) = tokens_at.insert(&ctx.pool).await {
+            send_error_to_moderator(ctx, format!("Error pushing in database 2 times{:?}", e));
+            run_error = Some(format!("Database insert: {}", e));
         }
     }
+    record_last_run(ctx, now, run_start, times, run_error).await;
+    update_demand_metrics(ctx).await;
     info!("SUCCESS");
 }
 
+async fn update_demand_metrics(ctx: &AppContext) {
+    match count_demands_by_type_and_interval(&ctx.pool).await {
+        Ok(counts) => {
+            DEMANDS.reset();
+            for (type_of, interval, count) in counts {
+                DEMANDS.with_label_values(&[&type_of, &interval]).set(count);
+            }
+        }
+        Err(e) => error!("{}", e),
+    }
+}
+
+async fn record_last_run(
+    ctx: &AppContext,
+    started: DateTime<Utc>,
+    run_start: Instant,
+    times: Vec<String>,
+    error: Option<String>,
+) {
+    ctx.status
+        .set_last_run(RunStatus {
+            started_at: started.timestamp(),
+            duration_ms: run_start.elapsed().as_millis() as i64,
+            times,
+            error,
+        })
+        .await;
+}
+
+/// Bad assets don't stop the run, the moderator gets the list with the reasons
+fn report_skipped_items(ctx: &AppContext, source: &str, report: &FetchReport) {
+    if report.failed.is_empty() {
+        return;
+    }
+    let details: Vec<String> = report.failed.iter().map(|e| e.to_string()).collect();
+    send_error_to_moderator(
+        ctx,
+        format!(
+            "{} fetch skipped {}/{} items:\n{}",
+            source,
+            report.failed.len(),
+            report.total,
+            details.join("\n")
+        ),
+    );
+}
+
+/// Names of the intervals due at `now`, stored with the snapshot
+pub fn matching_intervals(intervals: &IntervalsConfig, now: DateTime<Utc>) -> Vec<String> {
+    intervals
+        .iter()
+        .filter(|(_, cron_str)| is_time_matching(cron_str, now))
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// True when the schedule fired within the tolerance before `now`,
+/// so a job started a few seconds late still gets its intervals
 fn is_time_matching(cron_str: &str, now: DateTime<Utc>) -> bool {
     let schedule = match Schedule::from_str(cron_str) {
         Ok(s) => s,
@@ -78,5 +182,8 @@ fn is_time_matching(cron_str: &str, now: DateTime<Utc>) -> bool {
         }
     };
 
-    schedule.includes(now)
+    schedule
+        .after(&(now - chrono::Duration::seconds(CRON_MATCH_TOLERANCE_SECS)))
+        .next()
+        .is_some_and(|fired| fired <= now)
 }
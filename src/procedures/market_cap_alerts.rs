@@ -0,0 +1,123 @@
+use teloxide::types::{ChatId, MessageId, ThreadId};
+
+use crate::{
+    bot::{
+        send_alert, send_error_to_moderator,
+        utils::{escape_user_text, format_usd_compact},
+    },
+    constants::schedules::INTERVAL_15MIN,
+    context::AppContext,
+    db::services::{
+        demands::{get_demands_by_type, Demand},
+        tokens::TokensAt,
+    },
+    hyperliquid::fetch_price::TokenInfo,
+    procedures::perp_alerts::{pair_with_previous, previous_snapshot},
+    types::commands::MCAP,
+};
+
+const MCAP_HEADER: &str = "__*🏦 WAGMI Market Cap Alert*__:\n";
+const MCAP_ERR_HEADER: &str = "Error satisfying market cap demand for :";
+
+pub async fn execute_market_cap_demands(ctx: &AppContext, tokens_at: &TokensAt) {
+    if let Err(e) = satisfy_market_cap_demands(ctx, tokens_at).await {
+        send_error_to_moderator(ctx, format!("{MCAP_ERR_HEADER}{:?}", e));
+    }
+}
+
+async fn satisfy_market_cap_demands(ctx: &AppContext, tokens_at: &TokensAt) -> anyhow::Result<()> {
+    let demands = get_demands_by_type(&ctx.pool, MCAP).await?;
+    if demands.is_empty() {
+        return Ok(());
+    }
+
+    let previous = match previous_snapshot(ctx, INTERVAL_15MIN).await? {
+        Some(previous) => previous,
+        None => return Ok(()),
+    };
+    for (demand, now, then) in pair_with_previous(&demands, &tokens_at.tokens, &previous.tokens) {
+        if let Some(msg) = check_market_cap_cross(demand, now, then, ctx.referral_link()) {
+            send_alert(
+                ctx,
+                ChatId(demand.chat_id),
+                &msg,
+                demand.thread_id.map(|id| ThreadId(MessageId(id))),
+                &demand.type_of,
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn check_market_cap_cross(
+    demand: &Demand,
+    now: &TokenInfo,
+    then: &TokenInfo,
+    referral_link: &str,
+) -> Option<String> {
+    let level = demand.threshold_usd as f64;
+    let direction = if then.market_cap < level && now.market_cap >= level {
+        "above"
+    } else if then.market_cap >= level && now.market_cap < level {
+        "below"
+    } else {
+        return None;
+    };
+    Some(format!(
+        "{MCAP_HEADER}[{}]({}{}) market cap crossed {} {}: {}\n{}",
+        escape_user_text(&now.name),
+        referral_link,
+        now.pair_number.unwrap_or_default(),
+        direction,
+        format_usd_compact(level),
+        now.format_price(),
+        now.format_caps()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(market_cap: f64) -> TokenInfo {
+        TokenInfo {
+            name: "PURR".to_owned(),
+            full_name: None,
+            price: 0.2,
+            price_prev_24h: 0.2,
+            pair_number: Some(10_000),
+            market_cap,
+            fdv: None,
+            coin: "PURR/USDC".to_owned(),
+            token_id: String::new(),
+            pair: None,
+            other_pairs: Vec::new(),
+        }
+    }
+
+    fn demand(threshold_usd: i64) -> Demand {
+        Demand {
+            token: "1".to_owned(),
+            threshold_usd,
+            ..Demand::new(1, MCAP, None)
+        }
+    }
+
+    #[test]
+    fn market_cap_cross_above_and_below() {
+        let demand = demand(5_000_000_000);
+        let above = check_market_cap_cross(&demand, &token(5.1e9), &token(4.9e9), "")
+            .expect("crossed above");
+        assert!(above.contains("crossed above 5.00B$"), "{}", above);
+        let below = check_market_cap_cross(&demand, &token(4.9e9), &token(5.1e9), "")
+            .expect("crossed below");
+        assert!(below.contains("crossed below"), "{}", below);
+    }
+
+    #[test]
+    fn market_cap_without_cross_is_silent() {
+        let demand = demand(1_000_000);
+        assert!(check_market_cap_cross(&demand, &token(2e6), &token(1.5e6), "").is_none());
+        assert!(check_market_cap_cross(&demand, &token(5e5), &token(9e5), "").is_none());
+    }
+}
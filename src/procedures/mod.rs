@@ -1,3 +1,11 @@
+pub mod error_summary;
 pub mod fill_demands;
+pub mod heartbeat;
+pub mod liquidity_alerts;
+pub mod listing_alert;
 pub mod main;
+pub mod market_cap_alerts;
+pub mod perp_alerts;
 pub mod pump_alert;
+pub mod replay;
+pub mod wallet_watch;
@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+
+use teloxide::types::{ChatId, MessageId, ThreadId};
+
+use crate::{
+    bot::{send_alert, send_error_to_moderator},
+    constants::schedules::INTERVAL_15MIN,
+    context::AppContext,
+    db::services::{
+        demands::{fetch_demands_by_time_and_type, get_demands_by_type, Demand},
+        tokens::{fetch_latest_tokens_at, TokensAt},
+    },
+    hyperliquid::perp_data::PerpInfo,
+    procedures::pump_alert::diff_in_percent,
+    types::commands::{FUNDING_ABOVE, FUNDING_BELOW, FUNDING_FLIP, OI_CHANGE},
+};
+
+const FUNDING_HEADER: &str = "__*💰 WAGMI Funding Alert*__:\n";
+const OI_HEADER: &str = "__*📊 WAGMI Open Interest Alert*__:\n";
+const PERP_ERR_HEADER: &str = "Error satisfying perp demand for :";
+
+pub async fn execute_perp_demands(ctx: &AppContext, tokens_at: &TokensAt) {
+    let mut err_stack = PERP_ERR_HEADER.to_owned();
+
+    if let Err(e) = satisfy_funding_demands(ctx, tokens_at).await {
+        err_stack.push_str(&format!("funding {:?}", e));
+    }
+    for time in tokens_at.times.iter() {
+        if let Err(e) = satisfy_oi_demands_at(ctx, time, tokens_at).await {
+            err_stack.push_str(&format!("oi {:?}{:?}", time, e));
+        }
+    }
+
+    if err_stack != PERP_ERR_HEADER {
+        send_error_to_moderator(ctx, err_stack);
+    }
+}
+
+async fn satisfy_funding_demands(ctx: &AppContext, tokens_at: &TokensAt) -> anyhow::Result<()> {
+    let mut demands = get_demands_by_type(&ctx.pool, FUNDING_ABOVE).await?;
+    demands.extend(get_demands_by_type(&ctx.pool, FUNDING_BELOW).await?);
+    demands.extend(get_demands_by_type(&ctx.pool, FUNDING_FLIP).await?);
+    if demands.is_empty() {
+        return Ok(());
+    }
+
+    // Funding alerts fire on crossing, compared to the previous fetch
+    let previous = match previous_snapshot(ctx, INTERVAL_15MIN).await? {
+        Some(previous) => previous,
+        None => return Ok(()),
+    };
+    for (demand, now, then) in pair_with_previous(&demands, &tokens_at.perps, &previous.perps) {
+        if let Some(msg) = check_funding(demand, now, then) {
+            send_demand_message(ctx, demand, &msg);
+        }
+    }
+    Ok(())
+}
+
+async fn satisfy_oi_demands_at(
+    ctx: &AppContext,
+    time: &str,
+    tokens_at: &TokensAt,
+) -> anyhow::Result<()> {
+    let demands = fetch_demands_by_time_and_type(&ctx.pool, time, OI_CHANGE).await?;
+    if demands.is_empty() {
+        return Ok(());
+    }
+
+    let previous = match previous_snapshot(ctx, time).await? {
+        Some(previous) => previous,
+        None => {
+            info!("No last perp infos for {time}");
+            return Ok(());
+        }
+    };
+    for (demand, now, then) in pair_with_previous(&demands, &tokens_at.perps, &previous.perps) {
+        if let Some(msg) = check_open_interest(demand, now, then) {
+            send_demand_message(ctx, demand, &msg);
+        }
+    }
+    Ok(())
+}
+
+/// Latest stored snapshot for `time`, the previous fetch as long as the current one is not stored
+pub async fn previous_snapshot(ctx: &AppContext, time: &str) -> anyhow::Result<Option<TokensAt>> {
+    fetch_latest_tokens_at(&ctx.pool, time).await
+}
+
+/// Each demand with its token in the current and the previous snapshot, skipping tokens missing from either
+pub fn pair_with_previous<'a, T>(
+    demands: &'a [Demand],
+    now: &'a HashMap<String, T>,
+    then: &'a HashMap<String, T>,
+) -> impl Iterator<Item = (&'a Demand, &'a T, &'a T)> {
+    demands
+        .iter()
+        .filter_map(move |demand| Some((demand, now.get(&demand.token)?, then.get(&demand.token)?)))
+}
+
+pub fn check_funding(demand: &Demand, now: &PerpInfo, then: &PerpInfo) -> Option<String> {
+    let (annualized_now, annualized_then) = (now.annualized_funding(), then.annualized_funding());
+    let level = demand.percentage as f64;
+    let description = match demand.type_of.as_str() {
+        FUNDING_ABOVE if annualized_now > level && annualized_then <= level => {
+            format!("went above {}% annualized", demand.percentage)
+        }
+        FUNDING_BELOW if annualized_now < level && annualized_then >= level => {
+            format!("went below {}% annualized", demand.percentage)
+        }
+        FUNDING_FLIP if now.funding.signum() != then.funding.signum() && now.funding != 0.0 => {
+            let side = if now.funding > 0.0 {
+                "positive"
+            } else {
+                "negative"
+            };
+            format!("flipped {side}")
+        }
+        _ => return None,
+    };
+    Some(format!(
+        "{FUNDING_HEADER}*{}* funding {}: {:.2}% annualized, {:.4}% hourly",
+        now.name,
+        description,
+        annualized_now,
+        now.funding * 100.0
+    ))
+}
+
+pub fn check_open_interest(demand: &Demand, now: &PerpInfo, then: &PerpInfo) -> Option<String> {
+    let diff = diff_in_percent(now.open_interest, then.open_interest);
+    if diff == 0.0 || diff.abs() < demand.percentage as f64 {
+        return None;
+    }
+    let movement = if diff <= 0.0 { "dropped" } else { "risen" };
+    Some(format!(
+        "{OI_HEADER}*{}* open interest has {} by {:.2}% in the last {} : {:.0}$",
+        now.name,
+        movement,
+        diff,
+        demand.interval,
+        now.open_interest * now.mark_price
+    ))
+}
+
+fn send_demand_message(ctx: &AppContext, demand: &Demand, msg: &str) {
+    send_alert(
+        ctx,
+        ChatId(demand.chat_id),
+        msg,
+        demand.thread_id.map(|id| ThreadId(MessageId(id))),
+        &demand.type_of,
+    );
+}
@@ -1,14 +1,11 @@
-use chrono::Utc;
-
 use crate::{
-    bot::{broadcast_message, send_error_to_moderator},
-    constants::pumpcheck::{MIN_MARKET_CAP, OVER_SPECIAL_PERCENTAGE, SPECIAL_PERCENTAGE},
+    bot::{broadcast_message, send_error_to_moderator, utils::escape_user_text},
+    config::PumpConfig,
+    context::AppContext,
     db::services::demands::get_all_special_chat_id,
-    global_data::{
-        check_token_that_pumped, get_last_token_map, TokenThatPumped, REFERRAL_LINK,
-        TOKEN_THAT_PUMPED,
-    },
     hyperliquid::fetch_price::TokenInfo,
+    state::{MarketState, TokenMapping, TokenThatPumped},
+    types::commands::SPECIAL,
     // types::twitter_client::{utils, TwitterBot},
 };
 // use std::env;
@@ -16,46 +13,30 @@ use crate::{
 const PUMP_HEADER: &str = "__*📈 WAGMI Pump Alert:*__\n\n";
 const PUMP_ERROR_HEADER: &str = "PUMP_ERROR\n";
 
-pub async fn check_and_send_pump() {
+pub async fn check_and_send_pump(ctx: &AppContext) {
     // Generate alert message
-    let alert_message = match generate_pump_alert().await {
+    let alert_message = match generate_pump_alert(ctx).await {
         None => return,
         Some(message) => message,
     };
-    broadcast_to_chats(alert_message).await;
+    broadcast_to_chats(ctx, alert_message).await;
 }
 
 // Part 1: Generate pump alert message
-async fn generate_pump_alert() -> Option<String> {
-    let token_map = get_last_token_map().await;
+async fn generate_pump_alert(ctx: &AppContext) -> Option<String> {
+    let token_map = ctx.market.tokens().await;
     let mut alert_message = PUMP_HEADER.to_string();
-    let now = Utc::now().timestamp();
-    for (key, value) in token_map {
-        let mut pump = check_pump(&value);
-        if let Some(token_that_pumped) = check_token_that_pumped(&key).await {
-            if !check_over_pump(value.price, token_that_pumped.price) {
-                pump = 0.0;
-            }
-        }
-
-        if pump == 0.0 {
-            continue;
-        }
+    let now = ctx.clock.now().timestamp();
+    for (value, pump) in detect_pumps(&ctx.market, token_map, &ctx.config.pump, now).await {
         let message = &format!(
-            "__[{}]({}{})__: Price has risen by {}% in the last 24h: {}$\n------------------------\n",
-            key,
-            REFERRAL_LINK,
+            "__[{}]({}{})__: Price has risen by {}% in the last 24h: {}\n{}\n------------------------\n",
+            escape_user_text(&value.name),
+            ctx.referral_link(),
             value.pair_number.unwrap_or(0),
-            pump,value.price
+            pump,value.format_price(),
+            value.format_caps()
         );
 
-        let token = TokenThatPumped {
-            when: now,
-            price: value.price,
-        };
-        {
-            TOKEN_THAT_PUMPED.lock().await.insert(key, token);
-        }
         info!("{message}");
         alert_message.push_str(message);
     }
@@ -67,15 +48,50 @@ async fn generate_pump_alert() -> Option<String> {
     }
 }
 
-async fn broadcast_to_chats(alert_message: String) {
-    match get_all_special_chat_id().await {
-        Err(e) => send_error_to_moderator(format!(
-            "{PUMP_ERROR_HEADER}Error while getting all chat_ids: {:?}",
-            e
-        )),
+/// Pairs pumping at `now` with their rise in percent, recorded in `market` for the cooldown.
+/// Every quote of a token is checked. Shared with the replay, which keeps its own `MarketState`.
+pub async fn detect_pumps(
+    market: &MarketState,
+    token_map: TokenMapping,
+    pump_config: &PumpConfig,
+    now: i64,
+) -> Vec<(TokenInfo, f64)> {
+    let mut pumps = Vec::new();
+    for (key, token_info) in token_map.iter() {
+        for (key, value) in token_info.pairs(key) {
+            let mut pump = check_pump(value, pump_config);
+            if let Some(token_that_pumped) = market.check_token_that_pumped(key, now).await {
+                if !check_over_pump(value.price, token_that_pumped.price, pump_config) {
+                    pump = 0.0;
+                }
+            }
+
+            if pump == 0.0 {
+                continue;
+            }
+            let token = TokenThatPumped {
+                when: now,
+                price: value.price,
+            };
+            market.record_pump(key.to_owned(), token).await;
+            pumps.push((value.clone(), pump));
+        }
+    }
+    pumps
+}
+
+async fn broadcast_to_chats(ctx: &AppContext, alert_message: String) {
+    match get_all_special_chat_id(&ctx.pool).await {
+        Err(e) => send_error_to_moderator(
+            ctx,
+            format!(
+                "{PUMP_ERROR_HEADER}Error while getting all chat_ids: {:?}",
+                e
+            ),
+        ),
         Ok(all_chat_ids) => {
-            if let Err(e) = broadcast_message(all_chat_ids, alert_message).await {
-                send_error_to_moderator(format!("Error while getting all chat_ids: {:?}", e))
+            if let Err(e) = broadcast_message(ctx, all_chat_ids, alert_message, SPECIAL).await {
+                send_error_to_moderator(ctx, format!("Error while getting all chat_ids: {:?}", e))
             } else {
                 info!("Pump message broadcasted")
             }
@@ -91,16 +107,16 @@ pub fn diff_in_percent(now: f64, previous: f64) -> f64 {
     ((((now - previous) / previous * 1e4) as i32) / 100) as f64
 }
 
-pub fn check_pump(t: &TokenInfo) -> f64 {
+pub fn check_pump(t: &TokenInfo, pump: &PumpConfig) -> f64 {
     debug!("checking pump for {:?}", t.full_name);
     let mut ret = diff_in_percent(t.price, t.price_prev_24h);
-    if ret < SPECIAL_PERCENTAGE || t.market_cap < MIN_MARKET_CAP {
+    if ret < pump.special_percentage || t.market_cap < pump.min_market_cap {
         ret = 0.0;
     }
     ret
 }
-pub fn check_over_pump(now: f64, then: f64) -> bool {
-    (now - then) / then * 100.0 > OVER_SPECIAL_PERCENTAGE
+pub fn check_over_pump(now: f64, then: f64, pump: &PumpConfig) -> bool {
+    (now - then) / then * 100.0 > pump.over_special_percentage
 }
 
 // pub fn check_pump_level(increase: f64) -> u8 {
@@ -0,0 +1,292 @@
+// src/procedures/replay.rs
+
+use anyhow::{anyhow, bail};
+use chrono::{TimeZone, Utc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use crate::{
+    bot::utils::format_usd_compact,
+    config::Config,
+    db::services::{
+        demands::{get_demands_by_chat_id, Demand},
+        tokens::{fetch_tokens_at_between, TokensAt},
+    },
+    hyperliquid::fetch_price::TokenInfo,
+    init::init_pool,
+    procedures::{fill_demands::demand_diff, main::matching_intervals, pump_alert::detect_pumps},
+    state::{MarketState, TokenMapping},
+    types::commands::{ALERT, SPECIAL},
+};
+
+pub enum ReplaySource {
+    Database {
+        since: Option<i64>, // unix seconds
+        until: Option<i64>,
+    },
+    Candles(PathBuf), // lines of timestamp,token,price[,market_cap]
+}
+
+/// What to replay: the demands of a chat, a demand that doesn't exist yet, the pump check
+pub struct ReplayOptions {
+    pub source: ReplaySource,
+    pub chat_id: Option<i64>,
+    pub demand: Option<Demand>,
+    pub pump: bool,
+}
+
+pub struct FiredAlert {
+    pub at: i64,
+    pub line: String,
+}
+
+pub struct ReplayReport {
+    pub snapshots: usize,
+    pub fired: Vec<FiredAlert>,
+}
+
+impl ReplayReport {
+    pub fn print(&self) {
+        for alert in self.fired.iter() {
+            println!("{}  {}", format_time(alert.at), alert.line);
+        }
+        println!(
+            "{} alerts would have fired over {} snapshots",
+            self.fired.len(),
+            self.snapshots
+        );
+    }
+}
+
+/// Nothing is sent and nothing is written, the database is only read
+pub async fn run_replay(config: &Config, options: ReplayOptions) -> anyhow::Result<ReplayReport> {
+    let pool = match (&options.source, options.chat_id) {
+        (ReplaySource::Candles(_), None) => None,
+        _ => Some(init_pool(&config.database.url).await?),
+    };
+    let db = || {
+        pool.as_ref()
+            .ok_or_else(|| anyhow!("No database connection"))
+    };
+
+    let snapshots = match &options.source {
+        ReplaySource::Database { since, until } => {
+            fetch_tokens_at_between(
+                db()?,
+                since.map(|s| (s / 60) as i32),
+                until.map(|s| (s / 60) as i32),
+            )
+            .await?
+        }
+        ReplaySource::Candles(path) => {
+            let content = std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Cannot read {}: {}", path.display(), e))?;
+            snapshots_from_candles(config, &content)?
+        }
+    };
+
+    let mut demands = match options.chat_id {
+        Some(chat_id) => get_demands_by_chat_id(db()?, chat_id).await?,
+        None => Vec::new(),
+    };
+    let pump = options.pump || demands.iter().any(|d| d.type_of == SPECIAL);
+    demands.extend(options.demand);
+    let demands: Vec<Demand> = demands
+        .into_iter()
+        .filter(|d| d.type_of == ALERT)
+        .map(|d| Demand {
+            token: resolve_token(&snapshots, &d.token),
+            ..d
+        })
+        .collect();
+
+    Ok(replay(config, &snapshots, &demands, pump).await)
+}
+
+/// Same order as the main sequence: pumps, then each demand against
+/// the last snapshot stored for its interval
+async fn replay(
+    config: &Config,
+    snapshots: &[TokensAt],
+    demands: &[Demand],
+    pump: bool,
+) -> ReplayReport {
+    let market = MarketState::default();
+    let mut last_at: HashMap<String, Arc<TokenMapping>> = HashMap::new();
+    let mut fired = Vec::new();
+
+    for snapshot in snapshots {
+        let at = snapshot.timestamp_in_min as i64 * 60;
+        if pump {
+            for (token, rise) in
+                detect_pumps(&market, snapshot.tokens.clone(), &config.pump, at).await
+            {
+                fired.push(FiredAlert {
+                    at,
+                    line: format!(
+                        "pump  {} +{}% in 24h at {}$ (MC {})",
+                        token.name,
+                        rise,
+                        token.price,
+                        format_usd_compact(token.market_cap)
+                    ),
+                });
+            }
+        }
+
+        for demand in demands
+            .iter()
+            .filter(|d| snapshot.times.contains(&d.interval))
+        {
+            let previous = last_at
+                .get(&demand.interval)
+                .and_then(|tokens| tokens.get(&demand.token));
+            let (Some(previous), Some(new)) = (previous, snapshot.tokens.get(&demand.token)) else {
+                continue;
+            };
+            if let Some(diff) = demand_diff(demand, new, previous) {
+                fired.push(FiredAlert {
+                    at,
+                    line: format!(
+                        "{}  alert {} {}% {}: {:+.2}% ({}$ -> {}$)",
+                        demand_owner(demand),
+                        new.name,
+                        demand.percentage,
+                        demand.interval,
+                        diff,
+                        previous.price,
+                        new.price
+                    ),
+                });
+            }
+        }
+
+        let tokens = Arc::new(snapshot.tokens.clone());
+        for time in snapshot.times.iter() {
+            last_at.insert(time.clone(), tokens.clone());
+        }
+    }
+
+    ReplayReport {
+        snapshots: snapshots.len(),
+        fired,
+    }
+}
+
+fn demand_owner(demand: &Demand) -> String {
+    match demand.chat_id {
+        0 => "hypothetical".to_owned(),
+        chat_id => format!("chat {}", chat_id),
+    }
+}
+
+/// Demands use the token key, a name is looked up in the latest snapshot
+fn resolve_token(snapshots: &[TokensAt], token: &str) -> String {
+    let Some(latest) = snapshots.last() else {
+        return token.to_owned();
+    };
+    if latest.tokens.contains_key(token) {
+        return token.to_owned();
+    }
+    latest
+        .tokens
+        .iter()
+        .find(|(_, info)| info.name.eq_ignore_ascii_case(token))
+        .map(|(key, _)| key.clone())
+        .unwrap_or_else(|| token.to_owned())
+}
+
+/// One snapshot per minute found in the file, tokens keyed by the name given.
+/// The 24h price is the candle a day before, the intervals come from the config.
+fn snapshots_from_candles(config: &Config, content: &str) -> anyhow::Result<Vec<TokensAt>> {
+    let mut by_minute: BTreeMap<i32, TokenMapping> = BTreeMap::new();
+    for (line_no, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let timestamp: i64 = match fields[0].parse() {
+            Ok(timestamp) => timestamp,
+            Err(_) if line_no == 0 => continue, // header
+            Err(_) => bail!("line {}: bad timestamp '{}'", line_no + 1, fields[0]),
+        };
+        if fields.len() < 3 {
+            bail!(
+                "line {}: expected timestamp,token,price[,market_cap]",
+                line_no + 1
+            );
+        }
+        let price: f64 = fields[2]
+            .parse()
+            .map_err(|_| anyhow!("line {}: bad price '{}'", line_no + 1, fields[2]))?;
+        let market_cap: f64 = match fields.get(3) {
+            Some(value) => value
+                .parse()
+                .map_err(|_| anyhow!("line {}: bad market cap '{}'", line_no + 1, value))?,
+            None => 0.0,
+        };
+        by_minute
+            .entry((timestamp / 60) as i32)
+            .or_default()
+            .insert(
+                fields[1].to_owned(),
+                candle_token(fields[1], price, market_cap),
+            );
+    }
+
+    let prices: HashMap<(i32, String), f64> = by_minute
+        .iter()
+        .flat_map(|(minute, tokens)| {
+            tokens
+                .iter()
+                .map(move |(key, token)| ((*minute, key.clone()), token.price))
+        })
+        .collect();
+
+    let mut snapshots = Vec::new();
+    for (minute, mut tokens) in by_minute {
+        for (key, token) in tokens.iter_mut() {
+            if let Some(price) = prices.get(&(minute - 24 * 60, key.clone())) {
+                token.price_prev_24h = *price;
+            }
+        }
+        let now = Utc
+            .timestamp_opt(minute as i64 * 60, 0)
+            .single()
+            .ok_or_else(|| anyhow!("Timestamp out of range: {}", minute as i64 * 60))?;
+        snapshots.push(TokensAt {
+            timestamp_in_min: minute,
+            times: matching_intervals(&config.intervals, now),
+            tokens,
+            perps: Default::default(),
+        });
+    }
+    Ok(snapshots)
+}
+
+fn candle_token(name: &str, price: f64, market_cap: f64) -> TokenInfo {
+    TokenInfo {
+        name: name.to_owned(),
+        full_name: None,
+        price,
+        price_prev_24h: price, // no pump without the candle of the day before
+        pair_number: None,
+        market_cap,
+        fdv: None,
+        coin: String::new(),
+        token_id: String::new(),
+        pair: None,
+        other_pairs: Vec::new(),
+    }
+}
+
+fn format_time(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_else(|| timestamp.to_string())
+}
@@ -0,0 +1,276 @@
+use std::collections::HashMap;
+use teloxide::types::{ChatId, MessageId, ThreadId};
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+use crate::{
+    bot::{send_alert, send_error_to_moderator, utils::escape_user_text},
+    constants::schedules::CRON_WALLET_WATCH,
+    context::AppContext,
+    db::services::{
+        demands::{get_demands_by_type, Demand},
+        wallets::{delete_unwatched_wallets, fetch_wallet_state, save_wallet_state, WalletState},
+    },
+    hyperliquid::{
+        fetch_price::SpotUniverse,
+        user_state::{
+            fetch_ledger_updates, fetch_perp_positions, fetch_spot_balances, fetch_user_fills,
+            ledger_usd_value, PerpPosition, SpotBalance, UserFill,
+        },
+    },
+    metrics::SCHEDULER_RUN_DURATION,
+    types::commands::WATCH,
+};
+
+const WATCH_HEADER: &str = "__*👀 WAGMI Wallet Watch*__: ";
+const WATCH_ERROR_HEADER: &str = "WATCH_ERROR\n";
+
+pub async fn add_wallet_watch_sequence(scheduler: &JobScheduler, ctx: AppContext) {
+    scheduler
+        .add(
+            Job::new_async(CRON_WALLET_WATCH, move |_uuid, _l| {
+                let ctx = ctx.clone();
+                Box::pin(async move { check_watched_wallets(&ctx).await })
+            })
+            .unwrap(),
+        )
+        .await
+        .unwrap();
+}
+
+pub async fn check_watched_wallets(ctx: &AppContext) {
+    if ctx.status.is_paused() {
+        return;
+    }
+    let _timer = SCHEDULER_RUN_DURATION
+        .with_label_values(&["wallet_watch"])
+        .start_timer();
+    let demands = match get_demands_by_type(&ctx.pool, WATCH).await {
+        Ok(demands) => demands,
+        Err(e) => {
+            return send_error_to_moderator(
+                ctx,
+                format!(
+                    "{WATCH_ERROR_HEADER}Error while getting watch demands: {:?}",
+                    e
+                ),
+            )
+        }
+    };
+
+    let mut by_address: HashMap<String, Vec<Demand>> = HashMap::new();
+    for demand in demands {
+        by_address
+            .entry(demand.token.clone())
+            .or_default()
+            .push(demand);
+    }
+
+    let universe = ctx.market.universe().await;
+    let mut err_stack = String::new();
+    for (address, demands) in by_address {
+        match poll_wallet(ctx, &address, &universe).await {
+            Ok(events) if !events.is_empty() => {
+                for demand in demands {
+                    let name = demand
+                        .label
+                        .as_deref()
+                        .map(escape_user_text)
+                        .unwrap_or_else(|| short_address(&address));
+                    let msg = format!("{WATCH_HEADER}*{}*\n{}", name, events.concat());
+                    send_alert(
+                        ctx,
+                        ChatId(demand.chat_id),
+                        &msg,
+                        demand.thread_id.map(|id| ThreadId(MessageId(id))),
+                        WATCH,
+                    );
+                }
+            }
+            Ok(_) => {}
+            Err(e) => err_stack.push_str(&format!("{}: {:?}\n", address, e)),
+        }
+    }
+    if !err_stack.is_empty() {
+        send_error_to_moderator(ctx, format!("{WATCH_ERROR_HEADER}{err_stack}"));
+    }
+
+    if let Err(e) = delete_unwatched_wallets(&ctx.pool).await {
+        error!("{}", e);
+    }
+}
+
+async fn poll_wallet(
+    ctx: &AppContext,
+    address: &str,
+    universe: &SpotUniverse,
+) -> anyhow::Result<Vec<String>> {
+    let now = ctx.clock.now().timestamp_millis();
+    let info_url = &ctx.config.hyperliquid.info_url;
+    let (fills, spot_balances, perp_positions) = tokio::try_join!(
+        fetch_user_fills(info_url, address),
+        fetch_spot_balances(info_url, address),
+        fetch_perp_positions(info_url, address)
+    )?;
+    let holdings = holdings_from(&spot_balances, &perp_positions);
+
+    let previous = match fetch_wallet_state(&ctx.pool, address).await? {
+        Some(previous) => previous,
+        None => {
+            // First poll only records the starting point
+            save_wallet_state(
+                &ctx.pool,
+                &WalletState {
+                    address: address.to_owned(),
+                    last_fill_time: now,
+                    last_ledger_time: now,
+                    holdings,
+                },
+            )
+            .await?;
+            return Ok(Vec::new());
+        }
+    };
+    let ledger_updates =
+        fetch_ledger_updates(info_url, address, previous.last_ledger_time + 1).await?;
+
+    let mut events = Vec::new();
+
+    let mut new_fills: Vec<&UserFill> = fills
+        .iter()
+        .filter(|fill| fill.time > previous.last_fill_time)
+        .collect();
+    new_fills.sort_by_key(|fill| fill.time);
+    let config = &ctx.config;
+    let max_fills = config.quotas.max_fills_per_message;
+    for fill in new_fills.iter().take(max_fills) {
+        events.push(format_fill(fill, universe));
+    }
+    if new_fills.len() > max_fills {
+        events.push(format!("\\+{} more fills\n", new_fills.len() - max_fills));
+    }
+
+    for update in ledger_updates.iter() {
+        if let Some(value) = ledger_usd_value(&update.delta) {
+            if value >= config.thresholds.large_transfer_usd {
+                let kind = update
+                    .delta
+                    .get("type")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or("transfer");
+                events.push(format!("💸 {} of {:.0}$\n", escape_user_text(kind), value));
+            }
+        }
+    }
+
+    events.extend(diff_holdings(
+        &previous.holdings,
+        &holdings,
+        config.thresholds.min_holding_change_percent,
+    ));
+
+    let last_fill_time = new_fills
+        .last()
+        .map(|fill| fill.time)
+        .unwrap_or(previous.last_fill_time);
+    let last_ledger_time = ledger_updates
+        .iter()
+        .map(|update| update.time)
+        .max()
+        .unwrap_or(previous.last_ledger_time);
+    save_wallet_state(
+        &ctx.pool,
+        &WalletState {
+            address: address.to_owned(),
+            last_fill_time,
+            last_ledger_time,
+            holdings,
+        },
+    )
+    .await?;
+
+    Ok(events)
+}
+
+fn holdings_from(
+    spot_balances: &[SpotBalance],
+    perp_positions: &[PerpPosition],
+) -> HashMap<String, f64> {
+    let mut holdings = HashMap::new();
+    for balance in spot_balances {
+        if let Ok(total) = balance.total.parse::<f64>() {
+            holdings.insert(format!("spot:{}", balance.coin), total);
+        }
+    }
+    for position in perp_positions {
+        if let Ok(size) = position.szi.parse::<f64>() {
+            holdings.insert(format!("perp:{}", position.coin), size);
+        }
+    }
+    holdings
+}
+
+pub fn diff_holdings(
+    previous: &HashMap<String, f64>,
+    now: &HashMap<String, f64>,
+    min_change_percent: f64,
+) -> Vec<String> {
+    let mut keys: Vec<&String> = previous.keys().chain(now.keys()).collect();
+    keys.sort();
+    keys.dedup();
+
+    let mut events = Vec::new();
+    for key in keys {
+        let before = previous.get(key).cloned().unwrap_or(0.0);
+        let after = now.get(key).cloned().unwrap_or(0.0);
+        let changed = if before == 0.0 {
+            after != 0.0
+        } else {
+            ((after - before) / before * 100.0).abs() >= min_change_percent
+        };
+        if changed {
+            let (kind, coin) = key.split_once(':').unwrap_or(("", key));
+            events.push(format!(
+                "🔄 {} {}: {} → {}\n",
+                escape_user_text(coin),
+                kind,
+                before,
+                after
+            ));
+        }
+    }
+    events
+}
+
+fn format_fill(fill: &UserFill, universe: &SpotUniverse) -> String {
+    let mut line = format!(
+        "🔁 {} {} {} @ {}$",
+        escape_user_text(&fill.dir),
+        fill.sz,
+        escape_user_text(&display_coin(&fill.coin, universe)),
+        fill.px
+    );
+    if fill.closed_pnl.parse::<f64>().unwrap_or(0.0) != 0.0 {
+        line.push_str(&format!(" \\(PnL {}$\\)", fill.closed_pnl));
+    }
+    line.push('\n');
+    line
+}
+
+/// Spot fills come as `@N` or `PURR/USDC`, show the base token name instead
+fn display_coin(coin: &str, universe: &SpotUniverse) -> String {
+    universe
+        .pairs
+        .iter()
+        .find(|pair| pair.name == coin)
+        .and_then(|pair| pair.tokens.first())
+        .and_then(|index| universe.tokens.iter().find(|t| t.index as i32 == *index))
+        .map(|token| token.name.clone())
+        .unwrap_or_else(|| coin.to_owned())
+}
+
+fn short_address(address: &str) -> String {
+    if address.len() < 10 {
+        return address.to_owned();
+    }
+    format!("{}…{}", &address[..6], &address[address.len() - 4..])
+}
@@ -0,0 +1,48 @@
+// src/server.rs
+
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde_json::Value;
+
+use crate::{context::AppContext, health, metrics};
+
+/// Embedded HTTP server for the operational endpoints
+pub async fn serve(addr: String, ctx: AppContext) {
+    let app = Router::new()
+        .route("/metrics", get(metrics_handler))
+        .route("/healthz", get(healthz_handler))
+        .route("/readyz", get(readyz_handler))
+        .with_state(ctx);
+
+    let listener = match tokio::net::TcpListener::bind(&addr).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!("Could not bind the HTTP server on {}: {}", addr, e);
+            return;
+        }
+    };
+    info!("HTTP server listening on {}", addr);
+    if let Err(e) = axum::serve(listener, app).await {
+        error!("HTTP server stopped: {}", e);
+    }
+}
+
+async fn metrics_handler() -> String {
+    metrics::render()
+}
+
+async fn healthz_handler(State(ctx): State<AppContext>) -> (StatusCode, Json<Value>) {
+    with_status(health::liveness(&ctx))
+}
+
+async fn readyz_handler(State(ctx): State<AppContext>) -> (StatusCode, Json<Value>) {
+    with_status(health::readiness(&ctx).await)
+}
+
+fn with_status((ok, details): (bool, Value)) -> (StatusCode, Json<Value>) {
+    let status = if ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(details))
+}
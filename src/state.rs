@@ -0,0 +1,371 @@
+// src/state.rs
+
+use sqlx::{Pool, Postgres};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::atomic::{AtomicBool, AtomicI64, Ordering},
+};
+use teloxide::types::UserId;
+use tokio::sync::Mutex;
+
+use crate::{
+    constants::market_cap::SUPPLY_REFRESH_SECS,
+    context::AppContext,
+    db::services::chat::fetch_chat_demand_counts,
+    hyperliquid::{
+        error::{FetchError, FetchReport},
+        fetch_price::{fetch_token_data, SpotUniverse, TokenInfo},
+        perp_data::{fetch_perp_data, PerpInfo},
+        token_details::fetch_token_details,
+    },
+    metrics::{FETCH_DURATION, FETCH_FAILURES, FETCH_SKIPPED_ITEMS, PERPS_TRACKED, TOKENS_TRACKED},
+};
+
+pub type TokenMapping = HashMap<String, TokenInfo>; // by token index
+pub type PerpMapping = HashMap<String, PerpInfo>;
+
+#[derive(Clone)]
+pub struct TokenThatPumped {
+    pub when: i64,
+    pub price: f64,
+}
+#[derive(Clone)]
+pub struct TokenSupply {
+    pub fetched_at: i64,
+    pub supply: f64,
+}
+/// How the last fetch of a source went
+#[derive(Clone, Debug)]
+pub struct FetchHealth {
+    pub fetched_at: i64,
+    pub total: usize,
+    pub failed: usize,
+    pub error: Option<String>, // whole fetch failed
+    pub last_success_at: Option<i64>,
+}
+/// Outcome of the last main sequence
+#[derive(Clone, Debug)]
+pub struct RunStatus {
+    pub started_at: i64,
+    pub duration_ms: i64,
+    pub times: Vec<String>,
+    pub error: Option<String>,
+}
+/// Moderator errors of the same kind, see `bot::error_sender`
+#[derive(Clone, Debug, Default)]
+pub struct ErrorGroup {
+    pub count: u32,      // since first seen
+    pub suppressed: u32, // since last notification or summary
+    pub notified_at: i64,
+    pub last_seen: i64,
+    pub last_message: String,
+}
+pub type ErrorGroups = Mutex<HashMap<String, ErrorGroup>>;
+/// Who can do what in a chat, see `handlers::permissions`
+#[derive(Clone, Debug, Default)]
+pub struct ChatPermissions {
+    pub fetched_at: i64,
+    pub owner: Option<UserId>,
+    pub admins: HashSet<UserId>, // the owner included
+    pub managers: HashSet<UserId>,
+    pub public_commands: bool, // everyone can use the read-only commands
+}
+pub type ChatDemandMap = HashMap<i64, u8>;
+
+const H12: i64 = 60 * 60 * 12;
+const H24: i64 = H12 * 2;
+
+/// Last fetched market data and the caches derived from it
+#[derive(Default)]
+pub struct MarketState {
+    tokens: Mutex<TokenMapping>,
+    universe: Mutex<SpotUniverse>,
+    perps: Mutex<PerpMapping>,
+    supplies: Mutex<HashMap<String, TokenSupply>>,
+    pumped: Mutex<HashMap<String, TokenThatPumped>>,
+    liquidity_breaches: Mutex<HashSet<String>>,
+}
+
+impl MarketState {
+    pub async fn tokens(&self) -> TokenMapping {
+        self.tokens.lock().await.clone()
+    }
+    pub async fn perps(&self) -> PerpMapping {
+        self.perps.lock().await.clone()
+    }
+    pub async fn universe(&self) -> SpotUniverse {
+        self.universe.lock().await.clone()
+    }
+
+    /// Last pump of the token, forgotten after 24h
+    pub async fn check_token_that_pumped(&self, key: &str, now: i64) -> Option<TokenThatPumped> {
+        let mut pumped = self.pumped.lock().await;
+        match pumped.get(key) {
+            Some(token) if now - token.when >= H24 => {
+                pumped.remove(key);
+                None
+            }
+            token => token.cloned(),
+        }
+    }
+    pub async fn record_pump(&self, key: String, token: TokenThatPumped) {
+        self.pumped.lock().await.insert(key, token);
+    }
+
+    /// Record whether a liquidity demand is breached, true only when it just started
+    pub async fn update_liquidity_breach(&self, composite_id: String, breached: bool) -> bool {
+        let mut breaches = self.liquidity_breaches.lock().await;
+        if breached {
+            breaches.insert(composite_id)
+        } else {
+            breaches.remove(&composite_id);
+            false
+        }
+    }
+}
+
+/// Permissions of the chats, kept `permissions.admin_cache_ttl_secs`
+/// or until a member of the chat changes
+#[derive(Default)]
+pub struct PermissionCache {
+    chats: Mutex<HashMap<i64, ChatPermissions>>,
+}
+
+impl PermissionCache {
+    pub async fn get(&self, chat_id: i64, now: i64, ttl: i64) -> Option<ChatPermissions> {
+        self.chats
+            .lock()
+            .await
+            .get(&chat_id)
+            .filter(|permissions| now - permissions.fetched_at < ttl)
+            .cloned()
+    }
+    pub async fn insert(&self, chat_id: i64, permissions: ChatPermissions) {
+        self.chats.lock().await.insert(chat_id, permissions);
+    }
+    pub async fn invalidate(&self, chat_id: i64) {
+        self.chats.lock().await.remove(&chat_id);
+    }
+}
+
+/// Demand count of each chat, checked against the quota
+#[derive(Default)]
+pub struct ChatDemands {
+    counts: Mutex<ChatDemandMap>,
+}
+
+impl ChatDemands {
+    pub async fn get(&self, chat_id: i64) -> u8 {
+        self.counts.lock().await.get(&chat_id).cloned().unwrap_or(0)
+    }
+    pub async fn all(&self) -> ChatDemandMap {
+        self.counts.lock().await.clone()
+    }
+
+    pub async fn increase(&self, chat_id: i64) -> u8 {
+        let mut counts = self.counts.lock().await;
+        *counts
+            .entry(chat_id)
+            .and_modify(|count| *count += 1)
+            .or_insert(1)
+    }
+    pub async fn decrease(&self, chat_id: i64) -> u8 {
+        let mut counts = self.counts.lock().await;
+        let count = counts.entry(chat_id).or_insert(0);
+        *count = count.saturating_sub(1);
+        *count
+    }
+    pub async fn remove(&self, chat_id: i64) {
+        self.counts.lock().await.remove(&chat_id);
+    }
+
+    pub async fn reload(&self, pool: &Pool<Postgres>) -> anyhow::Result<()> {
+        let counts = fetch_chat_demand_counts(pool).await?;
+        *self.counts.lock().await = counts;
+        Ok(())
+    }
+}
+
+/// What the operator console and the health endpoints report on
+pub struct RuntimeStatus {
+    pub started_at: i64,
+    paused: AtomicBool, // scheduled jobs are skipped while set
+    dispatcher_running: AtomicBool,
+    heartbeat: AtomicI64,
+    last_run: Mutex<Option<RunStatus>>,
+    fetch_health: Mutex<HashMap<&'static str, FetchHealth>>,
+}
+
+impl RuntimeStatus {
+    pub fn new(started_at: i64) -> Self {
+        Self {
+            started_at,
+            paused: AtomicBool::new(false),
+            dispatcher_running: AtomicBool::new(false),
+            heartbeat: AtomicI64::new(0),
+            last_run: Mutex::new(None),
+            fetch_health: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl RuntimeStatus {
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst)
+    }
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::SeqCst);
+    }
+
+    pub fn dispatcher_running(&self) -> bool {
+        self.dispatcher_running.load(Ordering::SeqCst)
+    }
+    pub fn set_dispatcher_running(&self, running: bool) {
+        self.dispatcher_running.store(running, Ordering::SeqCst);
+    }
+
+    pub fn heartbeat(&self) -> i64 {
+        self.heartbeat.load(Ordering::SeqCst)
+    }
+    pub fn beat(&self, now: i64) {
+        self.heartbeat.store(now, Ordering::SeqCst);
+    }
+
+    pub async fn last_run(&self) -> Option<RunStatus> {
+        self.last_run.lock().await.clone()
+    }
+    pub async fn set_last_run(&self, run: RunStatus) {
+        *self.last_run.lock().await = Some(run);
+    }
+
+    pub async fn fetch_health(&self) -> HashMap<&'static str, FetchHealth> {
+        self.fetch_health.lock().await.clone()
+    }
+
+    async fn record_fetch_health<T>(
+        &self,
+        source: &'static str,
+        now: i64,
+        result: &Result<T, FetchError>,
+        report: Option<&FetchReport>,
+    ) {
+        let mut fetch_health = self.fetch_health.lock().await;
+        let last_success_at = match result {
+            Ok(_) => Some(now),
+            Err(_) => fetch_health
+                .get(source)
+                .and_then(|health| health.last_success_at),
+        };
+        let health = FetchHealth {
+            fetched_at: now,
+            total: report.map_or(0, |r| r.total),
+            failed: report.map_or(0, |r| r.failed.len()),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            last_success_at,
+        };
+        if health.error.is_some() {
+            FETCH_FAILURES.with_label_values(&[source]).inc();
+        }
+        FETCH_SKIPPED_ITEMS
+            .with_label_values(&[source])
+            .set(health.failed as i64);
+        if health.failed > 0 {
+            warn!(
+                "{source} fetch skipped {}/{} items",
+                health.failed, health.total
+            );
+        }
+        fetch_health.insert(source, health);
+    }
+}
+
+/// Returns the items skipped by the fetch, the caller decides how to report them
+pub async fn update_token_data(ctx: &AppContext) -> Result<FetchReport, FetchError> {
+    let timer = FETCH_DURATION.with_label_values(&["spot"]).start_timer();
+    let result = fetch_token_data(&ctx.config.hyperliquid.info_url).await;
+    timer.observe_duration();
+    ctx.status
+        .record_fetch_health(
+            "spot",
+            ctx.clock.now().timestamp(),
+            &result,
+            result.as_ref().ok().map(|r| &r.2),
+        )
+        .await;
+    let (mut map, spot_universe, report) = result?;
+    apply_fdv(ctx, &mut map).await;
+
+    TOKENS_TRACKED.set(map.len() as i64);
+    *ctx.market.tokens.lock().await = map;
+    *ctx.market.universe.lock().await = spot_universe;
+    debug!("Token map initiated");
+    Ok(report)
+}
+
+/// Fill `fdv` from the cached supplies, refreshing a few stale ones per run
+async fn apply_fdv(ctx: &AppContext, map: &mut TokenMapping) {
+    let now = ctx.clock.now().timestamp();
+    let supplies = &ctx.market.supplies;
+    let stale: Vec<String> = {
+        let cache = supplies.lock().await;
+        let mut tokens: Vec<&TokenInfo> = map
+            .values()
+            .filter(|token| !token.token_id.is_empty())
+            .filter(|token| {
+                cache
+                    .get(&token.token_id)
+                    .is_none_or(|supply| now - supply.fetched_at >= SUPPLY_REFRESH_SECS)
+            })
+            .collect();
+        tokens.sort_by(|a, b| b.market_cap.total_cmp(&a.market_cap));
+        tokens
+            .into_iter()
+            .take(ctx.config.quotas.max_supply_fetch_per_run)
+            .map(|token| token.token_id.clone())
+            .collect()
+    };
+
+    for token_id in stale {
+        match fetch_token_details(&ctx.config.hyperliquid.info_url, &token_id).await {
+            Ok(details) => {
+                if let Some(supply) = details.fdv_supply() {
+                    supplies.lock().await.insert(
+                        token_id,
+                        TokenSupply {
+                            fetched_at: now,
+                            supply,
+                        },
+                    );
+                }
+            }
+            Err(e) => warn!("No token details for {}: {}", token_id, e),
+        }
+    }
+
+    let cache = supplies.lock().await;
+    for token in map.values_mut() {
+        token.fdv = cache
+            .get(&token.token_id)
+            .map(|supply| supply.supply * token.price);
+    }
+}
+
+pub async fn update_perp_data(ctx: &AppContext) -> Result<FetchReport, FetchError> {
+    let timer = FETCH_DURATION.with_label_values(&["perp"]).start_timer();
+    let result = fetch_perp_data(&ctx.config.hyperliquid.info_url).await;
+    timer.observe_duration();
+    ctx.status
+        .record_fetch_health(
+            "perp",
+            ctx.clock.now().timestamp(),
+            &result,
+            result.as_ref().ok().map(|r| &r.1),
+        )
+        .await;
+    let (map, report) = result?;
+
+    PERPS_TRACKED.set(map.len() as i64);
+    *ctx.market.perps.lock().await = map;
+    debug!("Perp map updated");
+    Ok(report)
+}
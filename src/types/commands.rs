@@ -1,7 +1,20 @@
 use anyhow::anyhow;
-use teloxide::utils::command::BotCommands;
+use teloxide::{types::ChatId, utils::command::BotCommands};
+
+use crate::constants::market_cap::{MCAP_MAX_USD, MCAP_MIN_USD};
 pub const SPECIAL: &str = "pumpcheck";
 pub const ALERT: &str = "alert";
+pub const LISTING: &str = "listing";
+pub const WATCH: &str = "watch";
+pub const FUNDING_ABOVE: &str = "fundingabove";
+pub const FUNDING_BELOW: &str = "fundingbelow";
+pub const FUNDING_FLIP: &str = "fundingflip";
+pub const OI_CHANGE: &str = "oi";
+pub const SPREAD: &str = "spread";
+pub const DEPTH: &str = "depth";
+pub const MCAP: &str = "mcap";
+
+pub const SET_ALERT_CALLBACK_PREFIX: &str = "seta_";
 
 #[derive(BotCommands, Clone)]
 #[command(
@@ -23,27 +36,138 @@ pub enum Command {
     #[command(description = "Start or free the pump check.", parse_with = "default")]
     Special { switch: String },
 
+    #[command(
+        description = "Start or free the new listing alerts.",
+        parse_with = "default"
+    )]
+    Listing { switch: String },
+
+    #[command(description = "Watch a wallet.", parse_with = "default")]
+    Watch { str: String },
+
+    #[command(description = "Set a perp funding alert.", parse_with = "default")]
+    Funding { str: String },
+
+    #[command(
+        description = "Set a perp open interest alert.",
+        parse_with = "default"
+    )]
+    Oi { str: String },
+
+    #[command(description = "Set a spot spread alert.", parse_with = "default")]
+    Spread { str: String },
+
+    #[command(description = "Set a spot bid depth alert.", parse_with = "default")]
+    Depth { str: String },
+
+    #[command(description = "Set a market cap alert.", parse_with = "default")]
+    Mcap { str: String },
+
+    #[command(
+        description = "Export the demands as a JSON or CSV file.",
+        parse_with = "default"
+    )]
+    Export { format: String },
+
+    #[command(description = "Import the demands of an exported file, as a reply to it.")]
+    Import,
+
+    #[command(
+        description = "Copy the demands from or to chats you administer.",
+        parse_with = "default"
+    )]
+    Sync { str: String },
+
+    #[command(
+        description = "List, add or remove the alert managers.",
+        parse_with = "default"
+    )]
+    Manager { str: String },
+
+    #[command(
+        description = "Let everyone use /demands and /price.",
+        parse_with = "default"
+    )]
+    Public { switch: String },
+
+    #[command(description = "Show the price of a token.", parse_with = "default")]
+    Price { str: String },
+
     // #[command(description = "Delete all your alerts.")]
     // DeleteAlerts,
     #[command(description = "Sow explanation")]
     Help,
 }
 
-pub fn switch_type(switch: String) -> anyhow::Result<bool> {
+/// Only available to operators, in their DM with the bot
+#[derive(BotCommands, Clone)]
+#[command(rename_rule = "lowercase", description = "Operator commands:")]
+pub enum OperatorCommand {
+    #[command(description = "List chats with their demand count.")]
+    Chats,
+
+    #[command(description = "Show the demands of a chat.", parse_with = "default")]
+    ChatDemands { str: String },
+
+    #[command(description = "Delete a demand by its ID.", parse_with = "default")]
+    DeleteDemand { str: String },
+
+    #[command(description = "Delete every demand of a chat.", parse_with = "default")]
+    ClearDemands { str: String },
+
+    #[command(description = "Refetch tokens, perps and demand counts.")]
+    Refresh,
+
+    #[command(description = "Pause the scheduled jobs.")]
+    Pause,
+
+    #[command(description = "Resume the scheduled jobs.")]
+    Resume,
+
+    #[command(description = "Last run and fetch status.")]
+    Status,
+
+    #[command(
+        description = "Send an announcement to all chats.",
+        parse_with = "default"
+    )]
+    Broadcast { str: String },
+
+    #[command(description = "Moderator errors grouped by kind.")]
+    Errors,
+
+    #[command(description = "Look up an error reference.", parse_with = "default")]
+    ErrorRef { str: String },
+
+    #[command(description = "Show operator commands.")]
+    OpHelp,
+}
+
+impl Command {
+    /// Allowed to everyone in the chats that turned `/public` on
+    pub fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            Command::Demands | Command::Price { .. } | Command::Help
+        )
+    }
+}
+
+pub fn switch_type(switch: String, command: &str) -> anyhow::Result<bool> {
     match switch.to_lowercase().as_ref() {
         "off" | "stop" => Ok(false),
         "on" | "start" => Ok(true),
-        _ => Err(anyhow!("/special on or off")),
+        _ => Err(anyhow!("/{command} on or off")),
     }
 }
 
 const SPECIAL_PARSE_ERR:&str = "- `\n/setalert \\[TOKEN\\] \\[INTERVAL\\] Otional<\\PERCENTAGE\\>`\n → '/help for interval list'\n";
 pub fn parse_alert(input: String) -> anyhow::Result<(String, String, i16)> {
     let opts: Vec<&str> = input.split_ascii_whitespace().collect();
-    if opts.len() > 3 || opts.len() == 0 {
+    if opts.len() > 3 || opts.is_empty() {
         return Err(anyhow!(SPECIAL_PARSE_ERR));
     }
-    let token = opts.get(0).ok_or(anyhow!(SPECIAL_PARSE_ERR))?.to_owned();
+    let token = opts.first().ok_or(anyhow!(SPECIAL_PARSE_ERR))?.to_owned();
     let interval = opts.get(1).ok_or(anyhow!(SPECIAL_PARSE_ERR))?.to_owned();
     let percentage_str = opts.get(2).cloned().unwrap_or("0");
     debug!("{percentage_str}");
@@ -52,3 +176,217 @@ pub fn parse_alert(input: String) -> anyhow::Result<(String, String, i16)> {
         .map_err(|_| anyhow!("Percentage must be an int"))?;
     Ok((token.to_owned(), interval.to_owned(), percentage))
 }
+
+const WATCH_PARSE_ERR: &str = "- `\n/watch \\[0xADDRESS\\] Optional<LABEL>`\n";
+const MAX_LABEL_LEN: usize = 32;
+pub fn parse_watch(input: String) -> anyhow::Result<(String, Option<String>)> {
+    let mut opts = input.split_ascii_whitespace();
+    let address = opts.next().ok_or(anyhow!(WATCH_PARSE_ERR))?.to_lowercase();
+    let is_hex = address.len() == 42
+        && address.starts_with("0x")
+        && address[2..].chars().all(|c| c.is_ascii_hexdigit());
+    if !is_hex {
+        return Err(anyhow!("Invalid address: {}", address));
+    }
+    let label = opts.collect::<Vec<&str>>().join(" ");
+    if label.chars().count() > MAX_LABEL_LEN {
+        return Err(anyhow!("Label must be at most {MAX_LABEL_LEN} characters"));
+    }
+    let label = if label.is_empty() { None } else { Some(label) };
+    Ok((address, label))
+}
+
+const FUNDING_PARSE_ERR: &str =
+    "- `\n/funding \\[TOKEN\\] \\[above/below/flip\\] Optional<ANNUALIZED_PERCENTAGE>`\n";
+pub fn parse_funding(input: String) -> anyhow::Result<(String, &'static str, i16)> {
+    let opts: Vec<&str> = input.split_ascii_whitespace().collect();
+    let token = opts.first().ok_or(anyhow!(FUNDING_PARSE_ERR))?.to_string();
+    let condition = opts.get(1).ok_or(anyhow!(FUNDING_PARSE_ERR))?;
+    let type_of = match condition.to_lowercase().as_str() {
+        "above" => FUNDING_ABOVE,
+        "below" => FUNDING_BELOW,
+        "flip" => FUNDING_FLIP,
+        _ => return Err(anyhow!(FUNDING_PARSE_ERR)),
+    };
+    let percentage = match (type_of, opts.get(2)) {
+        (FUNDING_FLIP, None) => 0,
+        (FUNDING_FLIP, Some(_)) => return Err(anyhow!(FUNDING_PARSE_ERR)),
+        (_, Some(percentage_str)) => percentage_str
+            .parse()
+            .map_err(|_| anyhow!("Percentage must be an int"))?,
+        (_, None) => return Err(anyhow!(FUNDING_PARSE_ERR)),
+    };
+    if opts.len() > 3 {
+        return Err(anyhow!(FUNDING_PARSE_ERR));
+    }
+    Ok((token, type_of, percentage))
+}
+
+pub const SPREAD_PARSE_ERR: &str = "- `\n/spread \\[TOKEN\\] \\[BPS\\]`\n";
+pub const DEPTH_PARSE_ERR: &str = "- `\n/depth \\[TOKEN\\] \\[THOUSANDS_OF_USD\\]`\n";
+pub fn parse_threshold(input: String, parse_err: &'static str) -> anyhow::Result<(String, i16)> {
+    let opts: Vec<&str> = input.split_ascii_whitespace().collect();
+    if opts.len() != 2 {
+        return Err(anyhow!(parse_err));
+    }
+    let threshold: i16 = opts[1]
+        .parse()
+        .map_err(|_| anyhow!("Threshold must be an int"))?;
+    if threshold <= 0 {
+        return Err(anyhow!("Threshold must be above 0"));
+    }
+    Ok((opts[0].to_owned(), threshold))
+}
+
+#[derive(Debug, PartialEq)]
+pub enum SyncDirection {
+    From, // into this chat
+    To,   // from this chat
+}
+
+pub const MAX_SYNC_CHATS: usize = 10;
+const SYNC_PARSE_ERR: &str =
+    "- `\n/sync from \\[CHAT_ID\\]` or `/sync to \\[CHAT_ID\\] Optional<CHAT_ID ...>`\n";
+pub fn parse_sync(input: String) -> anyhow::Result<(SyncDirection, Vec<ChatId>)> {
+    let mut opts = input.split_ascii_whitespace();
+    let direction = match opts.next().map(str::to_lowercase).as_deref() {
+        Some("from") => SyncDirection::From,
+        Some("to") => SyncDirection::To,
+        _ => return Err(anyhow!(SYNC_PARSE_ERR)),
+    };
+    let chats = opts
+        .map(|chat| {
+            chat.parse()
+                .map(ChatId)
+                .map_err(|_| anyhow!("Invalid chat ID: {}", chat))
+        })
+        .collect::<anyhow::Result<Vec<ChatId>>>()?;
+    match (&direction, chats.len()) {
+        (_, 0) => Err(anyhow!(SYNC_PARSE_ERR)),
+        (SyncDirection::From, 2..) => Err(anyhow!("Copy from one chat at a time")),
+        (SyncDirection::To, n) if n > MAX_SYNC_CHATS => {
+            Err(anyhow!("{MAX_SYNC_CHATS} chats at most"))
+        }
+        _ => Ok((direction, chats)),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ManagerAction {
+    List,
+    Add,
+    Remove,
+}
+
+const MANAGER_PARSE_ERR: &str =
+    "- `\n/manager add/remove Optional<USER_ID>` → in reply to the user, or with their ID\n";
+pub fn parse_manager(input: String) -> anyhow::Result<(ManagerAction, Option<u64>)> {
+    let opts: Vec<&str> = input.split_ascii_whitespace().collect();
+    let action = match opts.first().map(|opt| opt.to_lowercase()).as_deref() {
+        None | Some("list") => ManagerAction::List,
+        Some("add") => ManagerAction::Add,
+        Some("remove") => ManagerAction::Remove,
+        _ => return Err(anyhow!(MANAGER_PARSE_ERR)),
+    };
+    let user_id = match opts.get(1) {
+        Some(id) => Some(id.parse().map_err(|_| anyhow!("Invalid user ID: {}", id))?),
+        None => None,
+    };
+    if opts.len() > 2 || (action == ManagerAction::List && user_id.is_some()) {
+        return Err(anyhow!(MANAGER_PARSE_ERR));
+    }
+    Ok((action, user_id))
+}
+
+const MCAP_PARSE_ERR: &str = "- `\n/mcap \\[TOKEN\\] \\[MARKET_CAP\\]` → e.g. 500k, 10m, 1.5b\n";
+/// Parse "500k" / "10m" / "1.5b" / "2500" after the token.
+/// Returns the token query and the level in USD
+pub fn parse_mcap(input: String) -> anyhow::Result<(String, i64)> {
+    let opts: Vec<&str> = input.split_ascii_whitespace().collect();
+    if opts.len() != 2 {
+        return Err(anyhow!(MCAP_PARSE_ERR));
+    }
+    let amount = opts[1].to_lowercase();
+    let (number, multiplier) = match amount.chars().last() {
+        Some('k') => (&amount[..amount.len() - 1], 1e3),
+        Some('m') => (&amount[..amount.len() - 1], 1e6),
+        Some('b') => (&amount[..amount.len() - 1], 1e9),
+        _ => (amount.as_str(), 1.0),
+    };
+    let value: f64 = number.parse().map_err(|_| anyhow!(MCAP_PARSE_ERR))?;
+    let usd = (value * multiplier).round();
+    check_mcap_level(usd)?;
+    Ok((opts[0].to_owned(), usd as i64))
+}
+
+/// Same bounds for `/mcap` and the imported demands
+pub fn check_mcap_level(usd: f64) -> anyhow::Result<()> {
+    if !(MCAP_MIN_USD..=MCAP_MAX_USD).contains(&usd) {
+        return Err(anyhow!(
+            "Market cap must be between {}k and {}t",
+            MCAP_MIN_USD / 1e3,
+            MCAP_MAX_USD / 1e12
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mcap_reads_suffixes() {
+        assert_eq!(
+            parse_mcap("PURR 500k".to_owned()).unwrap(),
+            ("PURR".to_owned(), 500_000)
+        );
+        assert_eq!(
+            parse_mcap("HYPE 1.5B".to_owned()).unwrap(),
+            ("HYPE".to_owned(), 1_500_000_000)
+        );
+        assert_eq!(
+            parse_mcap("HYPE 25b".to_owned()).unwrap(),
+            ("HYPE".to_owned(), 25_000_000_000)
+        );
+        assert_eq!(
+            parse_mcap("PURR 2500".to_owned()).unwrap(),
+            ("PURR".to_owned(), 2_500)
+        );
+    }
+
+    #[test]
+    fn parse_mcap_rejects_bad_input() {
+        assert!(parse_mcap("PURR".to_owned()).is_err());
+        assert!(parse_mcap("PURR 10x".to_owned()).is_err());
+        assert!(parse_mcap("PURR 10m extra".to_owned()).is_err());
+        assert!(parse_mcap("PURR 500".to_owned()).is_err());
+        assert!(parse_mcap("PURR 20000b".to_owned()).is_err());
+    }
+
+    #[test]
+    fn parse_sync_reads_direction_and_chats() {
+        assert_eq!(
+            parse_sync("FROM -1001".to_owned()).unwrap(),
+            (SyncDirection::From, vec![ChatId(-1001)])
+        );
+        assert_eq!(
+            parse_sync("to -1001 -1002".to_owned()).unwrap(),
+            (SyncDirection::To, vec![ChatId(-1001), ChatId(-1002)])
+        );
+    }
+
+    #[test]
+    fn parse_sync_rejects_bad_input() {
+        assert!(parse_sync(String::new()).is_err());
+        assert!(parse_sync("into -1001".to_owned()).is_err());
+        assert!(parse_sync("to".to_owned()).is_err());
+        assert!(parse_sync("to -1001 chat".to_owned()).is_err());
+        assert!(parse_sync("from -1001 -1002".to_owned()).is_err());
+
+        let too_many = ["-1001"; MAX_SYNC_CHATS + 1].join(" ");
+        assert!(parse_sync(format!("to {}", too_many)).is_err());
+        let most = ["-1001"; MAX_SYNC_CHATS].join(" ");
+        assert!(parse_sync(format!("to {}", most)).is_ok());
+    }
+}
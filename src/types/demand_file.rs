@@ -0,0 +1,189 @@
+// src/types/demand_file.rs
+
+use anyhow::anyhow;
+use serde::{Deserialize, Serialize};
+
+use crate::db::services::demands::Demand;
+
+/// One demand in an export, without the chat it belongs to.
+/// `name` is informative, the import goes by `token`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DemandRow {
+    #[serde(rename = "type")]
+    pub type_of: String,
+    pub token: String,
+    pub name: String,
+    pub percentage: i16,
+    pub interval: String,
+    pub threshold_usd: i64,
+    pub label: Option<String>,
+}
+
+impl DemandRow {
+    pub fn from_demand(demand: Demand, name: String) -> Self {
+        Self {
+            type_of: demand.type_of,
+            token: demand.token,
+            name,
+            percentage: demand.percentage,
+            interval: demand.interval,
+            threshold_usd: demand.threshold_usd,
+            label: demand.label,
+        }
+    }
+
+    pub fn into_demand(self, chat_id: i64, thread_id: Option<i32>) -> Demand {
+        Demand {
+            chat_id,
+            thread_id,
+            type_of: self.type_of,
+            token: self.token,
+            percentage: self.percentage,
+            interval: self.interval,
+            threshold_usd: self.threshold_usd,
+            label: self.label.filter(|label| !label.is_empty()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DemandFileFormat {
+    Json,
+    Csv,
+}
+
+impl DemandFileFormat {
+    /// `/export` argument, JSON when empty
+    pub fn parse(input: &str) -> Option<Self> {
+        match input.trim().to_lowercase().as_str() {
+            "" | "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let (_, extension) = file_name.rsplit_once('.')?;
+        match extension.to_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+        }
+    }
+}
+
+pub fn encode(rows: &[DemandRow], format: DemandFileFormat) -> anyhow::Result<Vec<u8>> {
+    match format {
+        DemandFileFormat::Json => Ok(serde_json::to_vec_pretty(rows)?),
+        DemandFileFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(Vec::new());
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer
+                .into_inner()
+                .map_err(|e| anyhow!("CSV write failed: {}", e))
+        }
+    }
+}
+
+/// Errors carry the row number, counted from 1 after the CSV header
+pub fn decode(content: &[u8], format: DemandFileFormat) -> anyhow::Result<Vec<DemandRow>> {
+    match format {
+        DemandFileFormat::Json => {
+            serde_json::from_slice(content).map_err(|e| anyhow!("Invalid JSON: {}", e))
+        }
+        DemandFileFormat::Csv => csv::Reader::from_reader(content)
+            .deserialize()
+            .enumerate()
+            .map(|(i, row)| row.map_err(|e| anyhow!("Row {}: {}", i + 1, e)))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows() -> Vec<DemandRow> {
+        vec![
+            DemandRow {
+                type_of: "alert".to_owned(),
+                token: "1".to_owned(),
+                name: "PURR".to_owned(),
+                percentage: 5,
+                interval: "1h".to_owned(),
+                ..DemandRow::default()
+            },
+            DemandRow {
+                type_of: "mcap".to_owned(),
+                token: "2".to_owned(),
+                name: "HFUN, the fun token".to_owned(),
+                threshold_usd: 2_500_000,
+                label: Some("moon".to_owned()),
+                ..DemandRow::default()
+            },
+        ]
+    }
+
+    fn assert_same(decoded: &[DemandRow], rows: &[DemandRow]) {
+        assert_eq!(decoded.len(), rows.len());
+        for (decoded, row) in decoded.iter().zip(rows) {
+            assert_eq!(decoded.type_of, row.type_of);
+            assert_eq!(decoded.token, row.token);
+            assert_eq!(decoded.name, row.name);
+            assert_eq!(decoded.percentage, row.percentage);
+            assert_eq!(decoded.interval, row.interval);
+            assert_eq!(decoded.threshold_usd, row.threshold_usd);
+            assert_eq!(decoded.label, row.label);
+        }
+    }
+
+    #[test]
+    fn json_and_csv_round_trip() {
+        for format in [DemandFileFormat::Json, DemandFileFormat::Csv] {
+            let content = encode(&rows(), format).unwrap();
+            assert_same(&decode(&content, format).unwrap(), &rows());
+        }
+    }
+
+    #[test]
+    fn missing_fields_take_their_default() {
+        let rows = decode(br#"[{"type": "listing"}]"#, DemandFileFormat::Json).unwrap();
+        assert_eq!(rows[0].type_of, "listing");
+        assert_eq!(rows[0].threshold_usd, 0);
+        assert_eq!(rows[0].label, None);
+    }
+
+    #[test]
+    fn csv_errors_name_the_row() {
+        let content = b"type,token,name,percentage,interval,threshold_usd,label\n\
+            alert,1,PURR,5,1h,0,\n\
+            alert,1,PURR,many,1h,0,\n";
+        let error = decode(content, DemandFileFormat::Csv).unwrap_err();
+        assert!(error.to_string().starts_with("Row 2:"), "{}", error);
+    }
+
+    #[test]
+    fn format_from_argument_and_file_name() {
+        assert_eq!(DemandFileFormat::parse(""), Some(DemandFileFormat::Json));
+        assert_eq!(
+            DemandFileFormat::parse(" CSV "),
+            Some(DemandFileFormat::Csv)
+        );
+        assert_eq!(DemandFileFormat::parse("xml"), None);
+        assert_eq!(
+            DemandFileFormat::from_file_name("demands_-1001.CSV"),
+            Some(DemandFileFormat::Csv)
+        );
+        assert_eq!(DemandFileFormat::from_file_name("demands"), None);
+    }
+}
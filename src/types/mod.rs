@@ -1,2 +1,3 @@
 pub mod commands;
+pub mod demand_file;
 // pub mod twitter_client;
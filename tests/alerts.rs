@@ -0,0 +1,149 @@
+// tests/alerts.rs
+
+mod common;
+
+use chrono::Duration;
+use common::{quarter, Harness, MockToken};
+use teloxide::types::{MessageId, ThreadId};
+use wagmi_alert::{
+    db::services::demands::Demand,
+    health::readiness,
+    types::commands::{ALERT, SPECIAL},
+};
+
+fn alert(
+    chat_id: i64,
+    thread_id: Option<i32>,
+    token: &str,
+    percentage: i16,
+    interval: &str,
+) -> Demand {
+    Demand {
+        token: token.to_string(),
+        percentage,
+        interval: interval.to_string(),
+        ..Demand::new(chat_id, ALERT, thread_id)
+    }
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn price_move_reaches_the_demand_thread() {
+    let harness = Harness::start().await;
+    harness
+        .market
+        .script(MockToken::new(1, "PURR", 1.0, &[1.0, 1.08]));
+    harness
+        .add_demand(alert(-1001, Some(7), "1", 5, "15min"))
+        .await;
+    harness
+        .add_demand(alert(-1002, None, "1", 10, "15min"))
+        .await;
+
+    harness.run_at(quarter(0)).await;
+    assert!(harness.sent_to(-1001).is_empty());
+
+    // A late tick still gets its intervals
+    harness.run_at(quarter(1) + Duration::seconds(20)).await;
+    let sent = harness.sent_to(-1001);
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].text.contains("risen by 8"), "{}", sent[0].text);
+    assert_eq!(sent[0].thread_id, Some(ThreadId(MessageId(7))));
+    assert!(harness.sent_to(-1002).is_empty());
+    assert!(harness.moderator_messages().is_empty());
+
+    harness.teardown().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn demand_compares_with_the_run_of_its_interval() {
+    let harness = Harness::start().await;
+    harness
+        .market
+        .script(MockToken::new(1, "PURR", 1.0, &[1.0, 0.9, 0.9]));
+    harness.add_demand(alert(-1003, None, "1", 5, "1h")).await;
+
+    // 10:00 is stored for 15min and 1h, 10:15 only for 15min
+    harness.run_at(quarter(0)).await;
+    harness.run_at(quarter(1)).await;
+    assert!(harness.sent_to(-1003).is_empty());
+
+    // 11:00 compares with 10:00
+    harness.run_at(quarter(4)).await;
+    let sent = harness.sent_to(-1003);
+    assert_eq!(sent.len(), 1);
+    assert!(
+        sent[0].text.contains("dropped by \\-10"),
+        "{}",
+        sent[0].text
+    );
+
+    harness.teardown().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn pump_is_broadcast_once() {
+    let harness = Harness::start().await;
+    harness
+        .market
+        .script(MockToken::new(1, "PURR", 1.0, &[2.0, 2.2]));
+    harness.add_demand(Demand::new(-1004, SPECIAL, None)).await;
+    harness
+        .add_demand(alert(-1005, None, "1", 50, "15min"))
+        .await;
+
+    harness.run_at(quarter(0)).await;
+    harness.run_at(quarter(1)).await;
+
+    let sent = harness.sent_to(-1004);
+    assert_eq!(sent.len(), 1);
+    assert!(sent[0].text.contains("Pump Alert"), "{}", sent[0].text);
+    assert!(sent[0].text.contains("PURR"), "{}", sent[0].text);
+    assert!(harness.sent_to(-1005).is_empty());
+
+    harness.teardown().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn pump_cooldown_expires_after_a_day() {
+    let harness = Harness::start().await;
+    harness
+        .market
+        .script(MockToken::new(1, "PURR", 1.0, &[2.0, 2.0, 2.0]));
+    harness.add_demand(Demand::new(-1006, SPECIAL, None)).await;
+
+    harness.run_at(quarter(0)).await;
+    harness.run_at(quarter(1)).await;
+    assert_eq!(harness.sent_to(-1006).len(), 1);
+
+    harness.run_at(quarter(0) + Duration::hours(24)).await;
+    assert_eq!(harness.sent_to(-1006).len(), 2);
+
+    harness.teardown().await;
+}
+
+#[tokio::test]
+#[ignore = "needs TEST_DATABASE_URL"]
+async fn readiness_follows_the_clock() {
+    let harness = Harness::start().await;
+    harness
+        .market
+        .script(MockToken::new(1, "PURR", 1.0, &[1.0]));
+
+    harness.run_at(quarter(0)).await;
+    let (ready, report) = readiness(&harness.ctx).await;
+    assert!(ready, "{}", report);
+
+    // Two quarters without a run, the token data and the sequence are late
+    harness.clock.set(quarter(2));
+    let (ready, report) = readiness(&harness.ctx).await;
+    assert!(!ready);
+    assert_eq!(report["token_data"], false);
+    assert_eq!(report["sequence"], false);
+    assert_eq!(report["database"], true);
+
+    harness.teardown().await;
+}
@@ -0,0 +1,301 @@
+// tests/common/mod.rs
+//
+// Harness driving the main sequence against a throwaway Postgres schema,
+// a scripted Hyperliquid info server and a recording messenger.
+// Needs TEST_DATABASE_URL, the tests are ignored by default:
+// `TEST_DATABASE_URL=postgres://... cargo test -- --ignored`
+
+#![allow(dead_code)]
+
+use std::{
+    env,
+    sync::{Arc, Mutex},
+};
+
+use axum::{extract::State, routing::post, Json, Router};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde_json::{json, Value};
+use sqlx::{postgres::PgPoolOptions, Executor, Pool, Postgres};
+use teloxide::{prelude::Bot, types::ChatId};
+use wagmi_alert::{
+    bot::messenger::{RecordedMessage, RecordingMessenger},
+    clock::ManualClock,
+    config::Config,
+    context::AppContext,
+    db::init_bdd::check_and_init_db,
+    db::services::demands::Demand,
+    procedures::main::execute_sequence,
+};
+
+pub const MODERATOR_ID: u64 = 42;
+const BOT_TOKEN: &str = "1:test";
+
+/// A Wednesday 10:00 UTC, every 15min and 1h demand is due
+pub fn quarter(n: i64) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 3, 10, 0, 0).unwrap() + Duration::minutes(15 * n)
+}
+
+/// A schema of its own per test, dropped by `teardown`
+pub struct TestDb {
+    pub pool: Pool<Postgres>,
+    admin: Pool<Postgres>,
+    schema: String,
+}
+
+impl TestDb {
+    pub async fn create(info_url: &str) -> Self {
+        let url = env::var("TEST_DATABASE_URL")
+            .expect("TEST_DATABASE_URL must point to a Postgres database");
+        let schema = format!("wagmi_test_{:x}", rand::random::<u64>());
+        let admin = PgPoolOptions::new()
+            .max_connections(1)
+            .connect(&url)
+            .await
+            .expect("Cannot connect to TEST_DATABASE_URL");
+        admin
+            .execute(format!("CREATE SCHEMA {}", schema).as_str())
+            .await
+            .expect("Cannot create the test schema");
+
+        let search_path = format!("SET search_path TO {}", schema);
+        let pool = PgPoolOptions::new()
+            .max_connections(4)
+            .after_connect(move |conn| {
+                let search_path = search_path.clone();
+                Box::pin(async move {
+                    conn.execute(search_path.as_str()).await?;
+                    Ok(())
+                })
+            })
+            .connect(&url)
+            .await
+            .expect("Cannot connect to the test schema");
+        check_and_init_db(&pool, info_url)
+            .await
+            .expect("Cannot create the tables");
+
+        Self {
+            pool,
+            admin,
+            schema,
+        }
+    }
+
+    pub async fn teardown(self) {
+        self.pool.close().await;
+        let _ = self
+            .admin
+            .execute(format!("DROP SCHEMA {} CASCADE", self.schema).as_str())
+            .await;
+    }
+}
+
+/// A spot token quoted against USDC, its mark price follows `prices` one step per run
+#[derive(Clone)]
+pub struct MockToken {
+    pub index: usize,
+    pub name: String,
+    pub prev_day_price: f64,
+    pub circulating_supply: f64,
+    pub prices: Vec<f64>,
+}
+
+impl MockToken {
+    pub fn new(index: usize, name: &str, prev_day_price: f64, prices: &[f64]) -> Self {
+        Self {
+            index,
+            name: name.to_string(),
+            prev_day_price,
+            circulating_supply: 1_000_000.0,
+            prices: prices.to_vec(),
+        }
+    }
+
+    fn token_id(&self) -> String {
+        token_id(self.index)
+    }
+
+    fn price_at(&self, step: usize) -> f64 {
+        let last = self.prices.len().saturating_sub(1);
+        self.prices.get(step.min(last)).cloned().unwrap_or(0.0)
+    }
+}
+
+fn token_id(index: usize) -> String {
+    format!("0x{:032x}", index)
+}
+
+#[derive(Default)]
+struct MockMarket {
+    tokens: Vec<MockToken>,
+    step: usize,
+}
+
+impl MockMarket {
+    fn spot(&self) -> Value {
+        let mut tokens = vec![json!({
+            "name": "USDC",
+            "szDecimals": 8,
+            "weiDecimals": 8,
+            "index": 0,
+            "tokenId": token_id(0),
+            "isCanonical": true,
+            "evmContract": null,
+            "fullName": null,
+        })];
+        let mut universe = Vec::new();
+        let mut ctxs = Vec::new();
+        for token in self.tokens.iter() {
+            let coin = format!("@{}", token.index);
+            let price = token.price_at(self.step).to_string();
+            tokens.push(json!({
+                "name": token.name,
+                "szDecimals": 2,
+                "weiDecimals": 8,
+                "index": token.index,
+                "tokenId": token.token_id(),
+                "isCanonical": false,
+                "evmContract": null,
+                "fullName": null,
+            }));
+            universe.push(json!({
+                "tokens": [token.index, 0],
+                "name": coin,
+                "index": token.index,
+                "isCanonical": false,
+            }));
+            ctxs.push(json!({
+                "prevDayPx": token.prev_day_price.to_string(),
+                "dayNtlVlm": "0.0",
+                "markPx": price,
+                "midPx": price,
+                "circulatingSupply": token.circulating_supply.to_string(),
+                "coin": coin,
+            }));
+        }
+        json!([{ "universe": universe, "tokens": tokens }, ctxs])
+    }
+
+    fn token_details(&self, token_id: &str) -> Value {
+        let token = self.tokens.iter().find(|t| t.token_id() == token_id);
+        json!({
+            "name": token.map(|t| t.name.clone()).unwrap_or_default(),
+            "maxSupply": null,
+            "totalSupply": token.map(|t| t.circulating_supply.to_string()),
+            "circulatingSupply": token.map(|t| t.circulating_supply.to_string()),
+        })
+    }
+}
+
+type SharedMarket = Arc<Mutex<MockMarket>>;
+
+/// Info endpoint answering from the scripted tokens, perps are empty
+pub struct MockHyperliquid {
+    market: SharedMarket,
+    pub info_url: String,
+}
+
+impl MockHyperliquid {
+    pub async fn start() -> Self {
+        let market = SharedMarket::default();
+        let app = Router::new()
+            .route("/info", post(info_handler))
+            .with_state(market.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Cannot bind the mock info server");
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+        Self {
+            market,
+            info_url: format!("http://{}/info", addr),
+        }
+    }
+
+    pub fn script(&self, token: MockToken) {
+        self.market.lock().unwrap().tokens.push(token);
+    }
+
+    pub fn advance(&self) {
+        self.market.lock().unwrap().step += 1;
+    }
+}
+
+async fn info_handler(State(market): State<SharedMarket>, Json(body): Json<Value>) -> Json<Value> {
+    let market = market.lock().unwrap();
+    match body["type"].as_str() {
+        Some("spotMetaAndAssetCtxs") => Json(market.spot()),
+        Some("metaAndAssetCtxs") => Json(json!([{ "universe": [] }, []])),
+        Some("tokenDetails") => Json(market.token_details(body["tokenId"].as_str().unwrap_or(""))),
+        _ => Json(Value::Null),
+    }
+}
+
+pub struct Harness {
+    pub ctx: AppContext,
+    pub messenger: Arc<RecordingMessenger>,
+    pub clock: Arc<ManualClock>,
+    pub market: MockHyperliquid,
+    db: TestDb,
+}
+
+impl Harness {
+    pub async fn start() -> Self {
+        let market = MockHyperliquid::start().await;
+        let db = TestDb::create(&market.info_url).await;
+
+        let mut config = Config::default();
+        config.telegram.token = BOT_TOKEN.to_string();
+        config.telegram.moderator_id = MODERATOR_ID;
+        config.hyperliquid.info_url = market.info_url.clone();
+        config.rate_limits.broadcast_interval_ms = 1;
+
+        let messenger = Arc::new(RecordingMessenger::new());
+        let clock = Arc::new(ManualClock::new(quarter(0)));
+        let ctx = AppContext::from_parts(
+            config,
+            Bot::new(BOT_TOKEN),
+            db.pool.clone(),
+            messenger.clone(),
+            clock.clone(),
+        )
+        .expect("Cannot build the test context");
+
+        Self {
+            ctx,
+            messenger,
+            clock,
+            market,
+            db,
+        }
+    }
+
+    pub async fn add_demand(&self, demand: Demand) {
+        demand
+            .insert_to_db(&self.ctx)
+            .await
+            .expect("Cannot insert the demand");
+    }
+
+    /// One main sequence at `now`, then the scripted prices move to their next step
+    pub async fn run_at(&self, now: DateTime<Utc>) {
+        self.clock.set(now);
+        execute_sequence(&self.ctx).await;
+        self.messenger.flush().await;
+        self.market.advance();
+    }
+
+    pub fn sent_to(&self, chat_id: i64) -> Vec<RecordedMessage> {
+        self.messenger.sent_to(ChatId(chat_id))
+    }
+
+    pub fn moderator_messages(&self) -> Vec<RecordedMessage> {
+        self.sent_to(MODERATOR_ID as i64)
+    }
+
+    pub async fn teardown(self) {
+        self.db.teardown().await;
+    }
+}